@@ -0,0 +1,369 @@
+// Aggregation functions used by stats.
+// To add a new aggregation, implement Aggregator and register its constructor in REGISTRY.
+
+#![allow(clippy::needless_return)]
+
+use std::collections::HashSet;
+
+use crate::value::Value;
+
+pub trait Aggregator {
+    // Called once for every non-null value in the group, and for every value of a multivalue.
+    // Aggregators that don't need a field are called once per row with null instead.
+    fn add(&mut self, value: &Value);
+
+    fn result(&self) -> Value;
+
+    fn requires_field(&self) -> bool {
+        return true;
+    }
+}
+
+type Constructor = fn() -> Box<dyn Aggregator>;
+
+const REGISTRY: &[(&str, Constructor)] = &[
+    ("count", || Box::new(Count::default())),
+    ("sum", || Box::new(Sum::default())),
+    ("avg", || Box::new(Avg::default())),
+    ("mean", || Box::new(Avg::default())),
+    ("min", || Box::new(Min::default())),
+    ("max", || Box::new(Max::default())),
+    ("distinct_count", || Box::new(DistinctCount::default())),
+    ("dc", || Box::new(DistinctCount::default())),
+    ("values", || Box::new(Values::default())),
+    ("list", || Box::new(List::default())),
+    ("first", || Box::new(First::default())),
+    ("last", || Box::new(Last::default())),
+    ("median", || Box::new(Percentile::new(50.0))),
+    ("stdev", || Box::new(Variance::sample(true))),
+    ("stdevp", || Box::new(Variance::population(true))),
+    ("var", || Box::new(Variance::sample(false))),
+    ("varp", || Box::new(Variance::population(false))),
+];
+
+// Looks up an aggregation by name, percentiles are written as perc<N> or p<N> (e.g. perc95, p99)
+pub fn create(name: &str) -> Option<Box<dyn Aggregator>> {
+    if let Some((_, constructor)) = REGISTRY.iter().find(|(n, _)| *n == name) {
+        return Some(constructor());
+    }
+
+    let percentile = name.strip_prefix("perc").or_else(|| name.strip_prefix('p'))?;
+    let percentile: f64 = percentile.parse().ok()?;
+
+    if (0.0..=100.0).contains(&percentile) {
+        return Some(Box::new(Percentile::new(percentile)));
+    }
+
+    return None;
+}
+
+#[derive(Default)]
+struct Count {
+    count: usize
+}
+
+impl Aggregator for Count {
+    fn add(&mut self, _value: &Value) {
+        self.count += 1;
+    }
+
+    fn result(&self) -> Value {
+        return Value::Int(self.count as i64);
+    }
+
+    fn requires_field(&self) -> bool {
+        return false;
+    }
+}
+
+// Stays an int as long as every value is an int (and the sum fits), null without any number
+struct Sum {
+    int_sum: Option<i64>,
+    sum: f64,
+    count: usize
+}
+
+impl Default for Sum {
+    fn default() -> Self {
+        return Self { int_sum: Some(0), sum: 0.0, count: 0 };
+    }
+}
+
+impl Aggregator for Sum {
+    fn add(&mut self, value: &Value) {
+        let Some(number) = value.as_number() else {
+            return;
+        };
+
+        self.sum += number;
+        self.count += 1;
+        self.int_sum = match value {
+            Value::Int(value) => self.int_sum.and_then(|sum| sum.checked_add(*value)),
+            _ => None
+        };
+    }
+
+    fn result(&self) -> Value {
+        if self.count == 0 {
+            return Value::Null;
+        }
+
+        return match self.int_sum {
+            Some(sum) => Value::Int(sum),
+            None => Value::Float(self.sum)
+        };
+    }
+}
+
+#[derive(Default)]
+struct Avg {
+    sum: f64,
+    count: usize
+}
+
+impl Aggregator for Avg {
+    fn add(&mut self, value: &Value) {
+        if let Some(value) = value.as_number() {
+            self.sum += value;
+            self.count += 1;
+        }
+    }
+
+    fn result(&self) -> Value {
+        if self.count == 0 {
+            return Value::Null;
+        }
+        return Value::Float(self.sum / self.count as f64);
+    }
+}
+
+#[derive(Default)]
+struct Min {
+    min: Option<Value>
+}
+
+impl Aggregator for Min {
+    fn add(&mut self, value: &Value) {
+        if self.min.as_ref().is_none_or(|min| value.order(min).is_lt()) {
+            self.min = Some(value.clone());
+        }
+    }
+
+    fn result(&self) -> Value {
+        return self.min.clone().unwrap_or(Value::Null);
+    }
+}
+
+#[derive(Default)]
+struct Max {
+    max: Option<Value>
+}
+
+impl Aggregator for Max {
+    fn add(&mut self, value: &Value) {
+        if self.max.as_ref().is_none_or(|max| value.order(max).is_gt()) {
+            self.max = Some(value.clone());
+        }
+    }
+
+    fn result(&self) -> Value {
+        return self.max.clone().unwrap_or(Value::Null);
+    }
+}
+
+#[derive(Default)]
+struct DistinctCount {
+    seen: HashSet<Value>
+}
+
+impl Aggregator for DistinctCount {
+    fn add(&mut self, value: &Value) {
+        self.seen.insert(value.clone());
+    }
+
+    fn result(&self) -> Value {
+        return Value::Int(self.seen.len() as i64);
+    }
+}
+
+// Distinct values, sorted
+#[derive(Default)]
+struct Values {
+    seen: HashSet<Value>
+}
+
+impl Aggregator for Values {
+    fn add(&mut self, value: &Value) {
+        self.seen.insert(value.clone());
+    }
+
+    fn result(&self) -> Value {
+        let mut values: Vec<Value> = self.seen.iter().cloned().collect();
+        values.sort_by(Value::order);
+        return Value::Multivalue(values);
+    }
+}
+
+// Every value, in pipeline order
+#[derive(Default)]
+struct List {
+    values: Vec<Value>
+}
+
+impl Aggregator for List {
+    fn add(&mut self, value: &Value) {
+        self.values.push(value.clone());
+    }
+
+    fn result(&self) -> Value {
+        return Value::Multivalue(self.values.clone());
+    }
+}
+
+#[derive(Default)]
+struct First {
+    first: Option<Value>
+}
+
+impl Aggregator for First {
+    fn add(&mut self, value: &Value) {
+        if self.first.is_none() {
+            self.first = Some(value.clone());
+        }
+    }
+
+    fn result(&self) -> Value {
+        return self.first.clone().unwrap_or(Value::Null);
+    }
+}
+
+#[derive(Default)]
+struct Last {
+    last: Option<Value>
+}
+
+impl Aggregator for Last {
+    fn add(&mut self, value: &Value) {
+        self.last = Some(value.clone());
+    }
+
+    fn result(&self) -> Value {
+        return self.last.clone().unwrap_or(Value::Null);
+    }
+}
+
+// Linear interpolation between the closest ranks
+struct Percentile {
+    percentile: f64,
+    values: Vec<f64>
+}
+
+impl Percentile {
+    fn new(percentile: f64) -> Self {
+        return Self { percentile, values: Vec::new() };
+    }
+}
+
+impl Aggregator for Percentile {
+    fn add(&mut self, value: &Value) {
+        if let Some(value) = value.as_number() {
+            self.values.push(value);
+        }
+    }
+
+    fn result(&self) -> Value {
+        if self.values.is_empty() {
+            return Value::Null;
+        }
+
+        let mut values = self.values.clone();
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        let rank = self.percentile / 100.0 * (values.len() - 1) as f64;
+        let lower = values[rank.floor() as usize];
+        let upper = values[rank.ceil() as usize];
+
+        return Value::Float(lower + (upper - lower) * rank.fract());
+    }
+}
+
+struct Variance {
+    values: Vec<f64>,
+    sample: bool,
+    deviation: bool
+}
+
+impl Variance {
+    fn sample(deviation: bool) -> Self {
+        return Self { values: Vec::new(), sample: true, deviation };
+    }
+
+    fn population(deviation: bool) -> Self {
+        return Self { values: Vec::new(), sample: false, deviation };
+    }
+}
+
+impl Aggregator for Variance {
+    fn add(&mut self, value: &Value) {
+        if let Some(value) = value.as_number() {
+            self.values.push(value);
+        }
+    }
+
+    fn result(&self) -> Value {
+        let count = self.values.len() as f64;
+        let divisor = if self.sample { count - 1.0 } else { count };
+
+        if divisor <= 0.0 {
+            return Value::Null;
+        }
+
+        let mean = self.values.iter().sum::<f64>() / count;
+        let variance = self.values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / divisor;
+
+        return Value::Float(if self.deviation { variance.sqrt() } else { variance });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aggregate(name: &str, values: &[Value]) -> Value {
+        let mut aggregator = create(name).unwrap();
+        values.iter().for_each(|value| aggregator.add(value));
+        return aggregator.result();
+    }
+
+    #[test]
+    fn sums_numbers_only() {
+        assert_eq!(aggregate("sum", &[Value::Int(1), "2".into(), Value::Int(3)]), Value::Float(6.0));
+        assert_eq!(aggregate("sum", &[Value::Int(1), Value::Int(3)]), Value::Int(4));
+        assert_eq!(aggregate("sum", &[Value::Int(i64::MAX), Value::Int(1)]), Value::Float(i64::MAX as f64 + 1.0));
+        assert_eq!(aggregate("sum", &["web".into()]), Value::Null);
+        assert_eq!(aggregate("sum", &[]), Value::Null);
+        assert_eq!(aggregate("avg", &[]), Value::Null);
+        assert_eq!(aggregate("count", &[]), Value::Int(0));
+    }
+
+    #[test]
+    fn collects_distinct_values() {
+        let values = [Value::Int(10), "b".into(), Value::Int(9), "b".into()];
+        assert_eq!(aggregate("values", &values), Value::Multivalue(vec![Value::Int(9), Value::Int(10), "b".into()]));
+        assert_eq!(aggregate("dc", &values), Value::Int(3));
+        assert_eq!(aggregate("list", &values), Value::Multivalue(values.to_vec()));
+        assert_eq!(aggregate("first", &values), Value::Int(10));
+        assert_eq!(aggregate("max", &[Value::Int(9), Value::Int(10)]), Value::Int(10));
+    }
+
+    #[test]
+    fn interpolates_percentiles() {
+        let values: Vec<Value> = (1..=5).map(Value::Int).collect();
+        assert_eq!(aggregate("median", &values), Value::Float(3.0));
+        assert_eq!(aggregate("p25", &values[..4]), Value::Float(1.75));
+        assert_eq!(aggregate("perc100", &values), Value::Float(5.0));
+        assert_eq!(aggregate("varp", &[Value::Int(1), Value::Int(3)]), Value::Float(1.0));
+        assert_eq!(aggregate("stdev", &[Value::Int(1)]), Value::Null);
+        assert!(create("p101").is_none());
+    }
+}
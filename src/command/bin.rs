@@ -0,0 +1,250 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, option, parse_count, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, time, value::Value};
+
+pub struct Bin;
+
+// Rounds the values of a column down to the start of their bucket, in place or into a new column.
+// Buckets are span wide and start at start (Default: 0, or the Unix epoch for timestamps).
+// With bins, the span is the smallest round number giving at most that many buckets.
+// Examples:
+// | bin latency span=50
+// | bin _time span=5m
+// | bin latency bins=20 as bucket
+// | bin price span=10 start=5
+impl CommandExecutor for Bin {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let usage = "Usage: bin <field> [span=<number>|bins=<number>] [start=<number>] [end=<number>] [as <field>]";
+
+        let mut span: Option<Value> = None;
+        let mut bins: Option<usize> = None;
+        let mut start: Option<Value> = None;
+        let mut end: Option<Value> = None;
+        let mut names: Vec<&String> = Vec::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    names.push(field_name(node)?);
+                    continue;
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "span" => span = Some(Self::number(name, value)?),
+                    "bins" => bins = Some(parse_count(name, value)?),
+                    "start" => start = Some(Self::bound(name, value)?),
+                    "end" => end = Some(Self::bound(name, value)?),
+                    _ => return Err(format!("Unknown option {} for bin", name).into())
+                }
+            }
+        }
+
+        let (field, target) = match names.as_slice() {
+            [field] => (*field, *field),
+            [field, keyword, target] if keyword.as_str() == "as" => (*field, *target),
+            _ => return Err(usage.into())
+        };
+
+        let source = state.get_column(field)
+            .map_err(|_| format!("Cannot bin {}: no such column", field))?;
+
+        let span = match (span, bins) {
+            (Some(_), Some(_)) => return Err("Options span and bins can't be used together".into()),
+            (Some(span), None) => span,
+            (None, bins) => {
+                if bins == Some(0) {
+                    return Err("Option bins expects a number greater than 0".into());
+                }
+                Self::auto_span(state, source, bins.unwrap_or(100), start.as_ref(), end.as_ref())
+            }
+        };
+
+        if span.as_number().is_none_or(|span| span <= 0.0) {
+            return Err(format!("Option span expects a number greater than 0, got {}", span).into());
+        }
+
+        let start = start.unwrap_or(Value::Int(0));
+        let buckets: Vec<Value> = state.rows().iter()
+            .map(|row| Self::bucket(&row[source], &span, &start))
+            .collect();
+
+        let column = state.get_or_create_column(target)?;
+        for (index, value) in buckets.into_iter().enumerate() {
+            state.set_cell(column, index, value)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Bin {
+    // A number or a duration such as 5m
+    fn number(name: &str, value: &str) -> Result<Value, Box<dyn Error>> {
+        return Value::parse_number(value)
+            .or_else(|| time::parse_duration(value))
+            .ok_or(format!("Option {} expects a number or a duration, got {}", name, value).into());
+    }
+
+    // A number, or a timestamp for time columns
+    fn bound(name: &str, value: &str) -> Result<Value, Box<dyn Error>> {
+        if let Some(number) = Value::parse_number(value) {
+            return Ok(number);
+        }
+        return time::parse_timestamp(value)
+            .map(Value::Timestamp)
+            .ok_or(format!("Option {} expects a number or a timestamp, got {}", name, value).into());
+    }
+
+    // Numbers are binned as they are, timestamps as epoch seconds
+    fn seconds(value: &Value) -> Option<f64> {
+        return match value {
+            Value::Timestamp(timestamp) => Some(*timestamp as f64 / 1000.0),
+            value => value.as_number()
+        };
+    }
+
+    // The start of the bucket holding the value, null for values that aren't numbers or timestamps
+    fn bucket(value: &Value, span: &Value, start: &Value) -> Value {
+        if let Value::Timestamp(timestamp) = value {
+            let span = time::from_seconds(span.as_number().unwrap()).filter(|span| *span > 0);
+            let start = match start {
+                Value::Timestamp(start) => Some(*start),
+                start => time::from_seconds(start.as_number().unwrap())
+            };
+
+            return match (span, start) {
+                (Some(span), Some(start)) => timestamp.checked_sub(start)
+                    .and_then(|offset| offset.div_euclid(span).checked_mul(span))
+                    .and_then(|offset| offset.checked_add(start))
+                    .map(Value::Timestamp)
+                    .unwrap_or(Value::Null),
+                _ => Value::Null
+            };
+        }
+
+        // Ints stay ints when the span and start are ints too
+        if let (Some(Value::Int(value)), Value::Int(span), Value::Int(start)) = (value.to_number(), span, start) {
+            let bucket = value.checked_sub(*start)
+                .and_then(|offset| offset.div_euclid(*span).checked_mul(*span))
+                .and_then(|offset| offset.checked_add(*start));
+
+            if let Some(bucket) = bucket {
+                return Value::Int(bucket);
+            }
+        }
+
+        let (span, start) = (span.as_number().unwrap(), Self::seconds(start).unwrap());
+        let value = match value.as_number() {
+            Some(value) => start + ((value - start) / span).floor() * span,
+            None => return Value::Null
+        };
+
+        // Round away float noise, e.g. 3 * 0.1 is 0.30000000000000004
+        let factor = 10_f64.powi(Self::decimals(span).max(Self::decimals(start)));
+        return Value::Float((value * factor).round() / factor);
+    }
+
+    // Number of decimals needed to write the number, at most 12
+    fn decimals(number: f64) -> i32 {
+        return (0..12)
+            .find(|decimals| {
+                let scaled = number * 10_f64.powi(*decimals);
+                (scaled - scaled.round()).abs() < 1e-9 * scaled.abs().max(1.0)
+            })
+            .unwrap_or(12);
+    }
+
+    // The smallest round span giving at most the given number of buckets over the values, start and end
+    fn auto_span(state: &Table, column: usize, bins: usize, start: Option<&Value>, end: Option<&Value>) -> Value {
+        let is_time = state.rows().iter().any(|row| matches!(row[column], Value::Timestamp(_)));
+        let origin = start.and_then(Self::seconds).unwrap_or(0.0);
+
+        let (mut low, mut high) = (f64::INFINITY, f64::NEG_INFINITY);
+        for value in state.rows().iter().map(|row| &row[column]).chain(start).chain(end) {
+            if let Some(value) = Self::seconds(value).filter(|value| value.is_finite()) {
+                low = low.min(value);
+                high = high.max(value);
+            }
+        }
+
+        if low >= high {
+            return Value::Int(1);
+        }
+
+        let fits = |span: f64| ((high - origin) / span).floor() - ((low - origin) / span).floor() < bins as f64;
+
+        // Times prefer spans that line up with the clock
+        if is_time {
+            let clock_spans = [1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1800, 3600, 7200, 10_800, 21_600, 43_200, 86_400, 604_800];
+            if let Some(span) = clock_spans.into_iter().find(|span| fits(*span as f64)) {
+                return Value::Int(span);
+            }
+        }
+
+        // 1, 2 and 5 times a power of ten
+        let mut exponent = ((high - low) / bins as f64).log10().floor().max(-300.0) as i32;
+        loop {
+            for multiple in [1.0, 2.0, 5.0] {
+                let span = multiple * 10_f64.powi(exponent);
+                if fits(span) || !span.is_finite() {
+                    return match span {
+                        span if span >= 1.0 && span < i64::MAX as f64 => Value::Int(span as i64),
+                        span => Value::Float(span)
+                    };
+                }
+            }
+            exponent += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{column, run};
+
+    fn ints(values: &[i64]) -> Vec<Value> {
+        return values.iter().map(|value| Value::Int(*value)).collect();
+    }
+
+    #[test]
+    fn rounds_down_to_the_start_of_the_bucket() {
+        let bucket = |value: Value, span: Value, start: Value| Bin::bucket(&value, &span, &start);
+
+        assert_eq!(bucket(Value::Int(49), Value::Int(50), Value::Int(0)), Value::Int(0));
+        assert_eq!(bucket(Value::Int(-1), Value::Int(50), Value::Int(0)), Value::Int(-50));
+        assert_eq!(bucket(Value::Int(7), Value::Int(10), Value::Int(5)), Value::Int(5));
+        assert_eq!(bucket(Value::Float(0.35), Value::Float(0.1), Value::Int(0)), Value::Float(0.3));
+        assert_eq!(bucket("12".into(), Value::Int(5), Value::Int(0)), Value::Int(10));
+        assert_eq!(bucket("web".into(), Value::Int(5), Value::Int(0)), Value::Null);
+        assert_eq!(bucket(Value::Timestamp(1_709_296_234_567), Value::Int(300), Value::Int(0)), Value::Timestamp(1_709_296_200_000));
+    }
+
+    #[test]
+    fn bins_a_column_in_place_or_into_another() {
+        let query = "| generate count=4 | eval n = $rowcount * 7";
+
+        let table = run(&format!("{} | bin n span=10", query)).unwrap();
+        assert_eq!(column(&table, "n"), ints(&[0, 10, 20, 20]));
+
+        let table = run(&format!("{} | bin n bins=3 as bucket", query)).unwrap();
+        assert_eq!(column(&table, "n"), ints(&[7, 14, 21, 28]));
+        assert_eq!(column(&table, "bucket"), ints(&[0, 10, 20, 20]));
+
+        let table = run("| generate count=2 | eval t = strptime(\"2024-03-01 12:07\", \"%Y-%m-%d %H:%M\") + $rowcount * 5m | bin t span=15m").unwrap();
+        assert_eq!(column(&table, "t"), [Value::Timestamp(1_709_294_400_000), Value::Timestamp(1_709_295_300_000)]);
+    }
+
+    #[test]
+    fn rejects_invalid_spans() {
+        let query = "| generate count=1 | eval n = 1";
+        assert!(run(&format!("{} | bin n span=0", query)).is_err());
+        assert!(run(&format!("{} | bin n span=1 bins=2", query)).is_err());
+        assert!(run(&format!("{} | bin n bins=0", query)).is_err());
+        assert!(run(&format!("{} | bin x span=1", query)).is_err());
+        assert!(run(&format!("{} | bin n span=1 to m", query)).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+#![allow(clippy::needless_return)]
+
+use std::{collections::HashMap, error::Error};
+
+use crate::{command::{field_name, option, parse_bool, parse_count, sort::Sort, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Dedup;
+
+// Removes rows whose values in the given fields were already seen.
+// With sortby, the rows are sorted first, so the sort decides which row of every duplicate survives.
+// Examples:
+// | dedup host
+// | dedup host, status keep=3
+// | dedup status consecutive=true
+// | dedup host sortby=-time
+impl CommandExecutor for Dedup {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut keep: usize = 1;
+        let mut consecutive = false;
+        let mut sort_by: Option<&ParseNode> = None;
+        let mut fields: Vec<&String> = Vec::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    fields.push(field_name(node)?);
+                    continue;
+                }
+
+                let name = node.child(0).and_then(|c| c.token_value()).ok_or("Option is missing a name")?;
+                match name.as_str() {
+                    "keep" => keep = parse_count(name, option(node)?.1)?,
+                    "consecutive" => consecutive = parse_bool(name, option(node)?.1)?,
+                    "sortby" => sort_by = node.child(1),
+                    _ => return Err(format!("Unknown option {} for dedup", name).into())
+                }
+            }
+        }
+
+        if fields.is_empty() {
+            return Err("Usage: dedup <field>[, <field>...] [keep=<number>] [consecutive=<bool>] [sortby=[+|-]<field>]".into());
+        }
+
+        if keep == 0 {
+            return Err("Option keep expects a number greater than 0".into());
+        }
+
+        let columns = fields.iter()
+            .map(|field| state.get_column(field).map_err(|_| format!("Cannot dedup {}: no such column", field).into()))
+            .collect::<Result<Vec<usize>, Box<dyn Error>>>()?;
+
+        let mut rows = state.rows().to_vec();
+
+        if let Some(sort_by) = sort_by {
+            let key = Sort::key(&[sort_by], state)?;
+            Sort::sort(&mut rows, &[key]);
+        }
+
+        let mut seen: HashMap<Vec<Value>, usize> = HashMap::new();
+        let mut previous: Option<Vec<Value>> = None;
+
+        rows.retain(|row| {
+            let key: Vec<Value> = columns.iter().map(|&i| row[i].clone()).collect();
+
+            // In consecutive mode, only the current run of equal rows counts
+            if consecutive && previous.as_ref() != Some(&key) {
+                seen.clear();
+                previous = Some(key.clone());
+            }
+
+            let count = seen.entry(key).or_insert(0);
+            *count += 1;
+            *count <= keep
+        });
+
+        state.set_data(state.columns().to_vec(), rows)?;
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    // v is 1 0 0 1 0 0
+    fn ids(query: &str) -> Vec<Value> {
+        return column(&run(&format!("| generate count=6 | eval id = $rowcount | eval v = $rowcount % 3 % 2 {}", query)).unwrap(), "id");
+    }
+
+    fn ints(values: &[i64]) -> Vec<Value> {
+        return values.iter().map(|value| Value::Int(*value)).collect();
+    }
+
+    #[test]
+    fn keeps_the_first_rows_of_every_value() {
+        assert_eq!(ids("| dedup v"), ints(&[1, 2]));
+        assert_eq!(ids("| dedup v keep=2"), ints(&[1, 2, 3, 4]));
+        assert_eq!(ids("| dedup v, id"), ints(&[1, 2, 3, 4, 5, 6]));
+    }
+
+    #[test]
+    fn dedups_runs_or_sorted_rows() {
+        assert_eq!(ids("| dedup v consecutive=true"), ints(&[1, 2, 4, 5]));
+        assert_eq!(ids("| dedup v sortby=-id"), ints(&[6, 4]));
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        assert!(run("| generate count=1 | dedup x").is_err());
+        assert!(run("| generate count=1 name=\"a\" | dedup a keep=0").is_err());
+        assert!(run("| generate count=1 name=\"a\" | dedup keep=1").is_err());
+    }
+}
@@ -1,6 +1,8 @@
+#![allow(clippy::needless_return)]
+
 use std::collections::HashMap;
 
-use crate::{command::CommandExecutor, output::Table, parser::{ParseNode, ParseNodeType}, scanner::TokenType};
+use crate::{command::CommandExecutor, output::Table, parser::{ParseNode, ParseNodeType}, scanner::TokenType, value::Value};
 
 pub struct Eval {}
 
@@ -14,11 +16,14 @@ impl CommandExecutor for Eval {
     fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn std::error::Error>> {
         // println!("Hello from eval!");
 
-        // Must be assignment
-        let argument = args[0]
-            .expect_type(ParseNodeType::Argument)?
-            .child(0).unwrap()
-            .expect_type(ParseNodeType::Assignment)?;
+        // Must be a single assignment, anything left after it is an error rather than dropped
+        let argument = match args {
+            [arg] => match arg.expect_type(ParseNodeType::Argument)?.children() {
+                [node] if node.variant == ParseNodeType::Assignment => node,
+                _ => return Err("Usage: eval <field> = <expression>".into())
+            },
+            _ => return Err("Usage: eval <field> = <expression>".into())
+        };
         
         // Must be identifier
         let identifier = argument
@@ -29,13 +34,16 @@ impl CommandExecutor for Eval {
             .or_else(|_| identifier.expect_token_type(TokenType::SpecialIdentifier))?
             .token_value().unwrap();
 
-        // Either expression or value, this should be calculated on every row
+        // Either expression, function call, value or condition, this should be calculated on every row
         let val_or_expr = argument
             .child(1).unwrap();
 
         let val_or_expr = val_or_expr
             .expect_type(ParseNodeType::Expression)
-            .or_else(|_| val_or_expr.expect_type(ParseNodeType::Value) )?;
+            .or_else(|_| val_or_expr.expect_type(ParseNodeType::Call))
+            .or_else(|_| val_or_expr.expect_type(ParseNodeType::Value))
+            .or_else(|_| val_or_expr.expect_type(ParseNodeType::Comparison))
+            .or_else(|_| val_or_expr.expect_type(ParseNodeType::Condition))?;
         
 
         let col_index = state.get_or_create_column(identifier)?;
@@ -44,11 +52,10 @@ impl CommandExecutor for Eval {
 
         // println!("Damn");
 
-        for i in 1..row_count {
-            let env: HashMap<String, String> = state.get_row_env(i)?;
+        for i in 0..row_count {
+            let env: HashMap<String, Value> = state.get_row_env(i)?;
             // println!("{:?}", env);
             let evaluated_val = <Eval as CommandExecutor>::evaluate(val_or_expr, &env)?; // What?
-            let evaluated_val = evaluated_val.token_value().unwrap();
             state.set_cell(col_index, i, evaluated_val)?;
         }
         // for (index, cell) in col.into_iter().enumerate() {
@@ -82,4 +89,23 @@ impl CommandExecutor for Eval {
 
         return Ok(());
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, value::Value};
+
+    #[test]
+    fn rejects_anything_but_a_single_assignment() {
+        assert!(run("| generate count=1 | eval").is_err());
+        assert!(run("| generate count=1 | eval a = 5m 3").is_err());
+        assert!(run("| generate count=1 | eval a = 1, 2").is_err());
+        assert_eq!(run("| generate count=1 | eval a = 5m -3").unwrap().rows()[0][1], Value::Int(297));
+    }
+    #[test]
+    fn takes_one_assignment_per_eval() {
+        assert!(run("| generate count=1 | eval b = 1, c = 2").is_err());
+        assert!(run("| generate count=1 | eval b = 1 c = 2").is_err());
+        assert_eq!(run("| generate count=1 | eval b = 1 | eval c = b * 2").unwrap().rows()[0][2], Value::Int(2));
+    }
+}
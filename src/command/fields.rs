@@ -0,0 +1,67 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, remove::Remove, table::Table, CommandExecutor}, output, parser::{ParseNode, ParseNodeType}, scanner::TokenType};
+
+pub struct Fields;
+
+// Keeps (+, the default) or removes (-) the listed columns
+// Examples:
+// | fields a, b
+// | fields + a, b
+// | fields - x, y*
+impl CommandExecutor for Fields {
+    fn execute(args: &[ParseNode], state: &mut output::Table) -> Result<(), Box<dyn Error>> {
+        let usage = "Usage: fields [+|-] <field>[, <field>...]";
+
+        let mut exclude = false;
+        let mut patterns: Vec<&String> = Vec::new();
+
+        for (position, arg) in args.iter().enumerate() {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::ArithmeticOperator {
+                    patterns.push(field_name(node)?);
+                    continue;
+                }
+
+                // The sign may only come first
+                if position > 0 || !patterns.is_empty() {
+                    return Err(usage.into());
+                }
+                exclude = node.token_type() == TokenType::Sub;
+            }
+        }
+
+        if patterns.is_empty() {
+            return Err(usage.into());
+        }
+
+        if exclude {
+            return Remove::remove(&patterns, false, "fields", state);
+        }
+
+        return Table::project(&patterns, "fields", state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::run;
+
+    const TABLE: &str = "| generate count=1 name=\"a\" | eval src_ip = 1 | eval b = 2 | eval src_port = 3";
+
+    #[test]
+    fn keeps_or_removes_the_listed_columns() {
+        assert_eq!(run(&format!("{} | fields b, a", TABLE)).unwrap().columns(), ["b", "a"]);
+        assert_eq!(run(&format!("{} | fields + src_*", TABLE)).unwrap().columns(), ["src_ip", "src_port"]);
+        assert_eq!(run(&format!("{} | fields - src_*, a", TABLE)).unwrap().columns(), ["b"]);
+    }
+
+    #[test]
+    fn only_accepts_a_sign_first() {
+        assert!(run(&format!("{} | fields a, - b", TABLE)).is_err());
+        assert!(run(&format!("{} | fields -", TABLE)).is_err());
+        assert!(run(&format!("{} | fields - x", TABLE)).is_err());
+    }
+}
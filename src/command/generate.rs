@@ -1,4 +1,6 @@
-use crate::{command::CommandExecutor, output::Table, parser::{ParseNode, ParseNodeType}};
+#![allow(clippy::needless_return)]
+
+use crate::{command::CommandExecutor, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
 
 pub struct Generate {}
 
@@ -37,14 +39,12 @@ impl CommandExecutor for Generate {
 
         // println!("{:?}", name_arg);
 
-        let name = name_arg.unwrap_or(format!("${}", 1).into());
+        let name = name_arg.unwrap_or(format!("${}", 1));
         
         state.add_col(&name, None);
         
         for _ in 0..count {
-            let mut e = Vec::new();
-            e.push(String::from("<Empty>"));
-            state.try_add_row(e)?;
+            state.try_add_row(vec![Value::Null])?;
         }
         
         return Ok(());
@@ -0,0 +1,125 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{option, parse_count, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, scanner::TokenType};
+
+pub struct Head;
+
+pub enum Limit<'a> {
+    Count(usize),
+    // Keep rows while the condition holds
+    While(&'a ParseNode),
+    // Keep rows until the condition holds
+    Until(&'a ParseNode)
+}
+
+// Keeps the first rows of the table, either a fixed number of them or up to the first row failing a predicate
+// Examples:
+// | head
+// | head 5
+// | head while=(status == 200)
+// | head until=(bytes > 1000)
+impl CommandExecutor for Head {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let row_count = state.rows().len();
+
+        let end = match Self::limit(args, "head")? {
+            Limit::Count(count) => count,
+            Limit::While(condition) => Self::first_row(state, 0..row_count, condition, false)?.unwrap_or(row_count),
+            Limit::Until(condition) => Self::first_row(state, 0..row_count, condition, true)?.unwrap_or(row_count)
+        };
+
+        state.keep_rows(0, end);
+
+        return Ok(());
+    }
+}
+
+impl Head {
+    // <number>
+    // count=<number>
+    // while=(<condition>)
+    // until=(<condition>)
+    pub fn limit<'a>(args: &'a [ParseNode], command: &str) -> Result<Limit<'a>, Box<dyn Error>> {
+        let usage = format!("Usage: {} [<number>|count=<number>|while=(<condition>)|until=(<condition>)]", command);
+
+        let node = match args {
+            [] => return Ok(Limit::Count(10)),
+            [arg] => match arg.expect_type(ParseNodeType::Argument)?.children() {
+                [node] => node,
+                _ => return Err(usage.into())
+            },
+            _ => return Err(usage.into())
+        };
+
+        if node.variant == ParseNodeType::Value && node.token_type() == TokenType::Number {
+            return Ok(Limit::Count(parse_count("count", node.token_value().unwrap())?));
+        }
+
+        if node.variant != ParseNodeType::Assignment {
+            return Err(usage.into());
+        }
+
+        let name = node.child(0).and_then(|c| c.token_value()).ok_or("Option is missing a name")?;
+        let value = node.child(1).ok_or(format!("Option {} is missing a value", name))?;
+
+        return match name.as_str() {
+            "count" => Ok(Limit::Count(parse_count(name, option(node)?.1)?)),
+            "while" => Ok(Limit::While(Self::condition(value)?)),
+            "until" => Ok(Limit::Until(Self::condition(value)?)),
+            _ => Err(format!("Unknown option {} for {}", name, command).into())
+        };
+    }
+
+    fn condition(node: &ParseNode) -> Result<&ParseNode, Box<dyn Error>> {
+        if matches!(node.variant, ParseNodeType::Comparison | ParseNodeType::Condition | ParseNodeType::Call | ParseNodeType::Value) {
+            return Ok(node);
+        }
+        return Err("Expected a condition in parentheses, e.g. while=(status == 200)".into());
+    }
+
+    // Returns the index of the first data row (visited in the given order) for which the condition equals `expected`
+    pub fn first_row(state: &Table, rows: impl Iterator<Item = usize>, condition: &ParseNode, expected: bool) -> Result<Option<usize>, Box<dyn Error>> {
+        for index in rows {
+            let env = state.get_row_env(index)?;
+            if <Head as CommandExecutor>::test(condition, &env)? == expected {
+                return Ok(Some(index));
+            }
+        }
+
+        return Ok(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    fn ids(query: &str) -> Vec<Value> {
+        return column(&run(&format!("| generate count=12 | eval id = $rowcount {}", query)).unwrap(), "id");
+    }
+
+    #[test]
+    fn keeps_the_first_rows() {
+        assert_eq!(ids("| head").len(), 10);
+        assert_eq!(ids("| head 2"), [Value::Int(1), Value::Int(2)]);
+        assert_eq!(ids("| head count=1"), [Value::Int(1)]);
+        assert_eq!(ids("| head 0"), []);
+        assert_eq!(ids("| head 20").len(), 12);
+    }
+
+    #[test]
+    fn stops_at_the_first_row_failing_the_predicate() {
+        assert_eq!(ids("| head while=(id % 4 != 3)"), [Value::Int(1), Value::Int(2)]);
+        assert_eq!(ids("| head until=(id > 1)"), [Value::Int(1)]);
+        assert_eq!(ids("| head while=(id > 0)").len(), 12);
+    }
+
+    #[test]
+    fn rejects_invalid_limits() {
+        assert!(run("| generate count=1 | head -1").is_err());
+        assert!(run("| generate count=1 | head 1 2").is_err());
+        assert!(run("| generate count=1 | head rows=1").is_err());
+    }
+}
@@ -0,0 +1,211 @@
+#![allow(clippy::needless_return)]
+
+use std::{error::Error, fs};
+
+use crate::{command::{option, parse_bool, parse_count, CommandExecutor}, csv::{Format, Reader, Record}, output::Table, parser::{ParseNode, ParseNodeType}, time, value::Value};
+
+pub struct Inputcsv;
+
+// Replaces the table with the records of a CSV file.
+// Every column gets the type all of its values have (int, float, bool or timestamp), or stays text, empty fields are null.
+// Examples:
+// | inputcsv path="access.csv"
+// | inputcsv path="export.tsv" delim="\t" header=false max_rows=100
+impl CommandExecutor for Inputcsv {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut path: Option<&String> = None;
+        let mut header = true;
+        let mut max_rows: Option<usize> = None;
+        let mut format = Format { delimiter: ',', quote: Some('"') };
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    return Err("Usage: inputcsv path=<string> [header=<bool>] [delim=<char>] [quote=<char>] [max_rows=<number>]".into());
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "path" => path = Some(value),
+                    "header" => header = parse_bool(name, value)?,
+                    "max_rows" => max_rows = Some(parse_count(name, value)?),
+                    "delim" => format.delimiter = Self::character(name, value)?.ok_or("Option delim expects a single character")?,
+                    "quote" => format.quote = Self::character(name, value)?,
+                    _ => return Err(format!("Unknown option {} for inputcsv", name).into())
+                }
+            }
+        }
+
+        let path = path.ok_or("Option path is required for inputcsv")?;
+        if Some(format.delimiter) == format.quote || matches!(format.delimiter, '\n' | '\r') {
+            return Err("Option delim can't be a quote or a line break".into());
+        }
+
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read {}: {}", path, err))?;
+
+        let mut reader = Reader::new(&text, &format);
+        let mut columns: Vec<String> = Vec::new();
+        let mut records: Vec<Vec<String>> = Vec::new();
+
+        if header {
+            if let Some(Record { line, fields: names }) = reader.next_record().map_err(|err| format!("{} {}", path, err))? {
+                for (index, name) in names.into_iter().enumerate() {
+                    // Nameless columns are numbered like the column of generate
+                    let name = if name.is_empty() { format!("${}", index + 1) } else { name };
+                    if columns.contains(&name) {
+                        return Err(format!("{} line {}: column {} appears twice in the header", path, line, name).into());
+                    }
+                    columns.push(name);
+                }
+            }
+        }
+
+        while max_rows.is_none_or(|max_rows| records.len() < max_rows) {
+            let Record { line, fields } = match reader.next_record().map_err(|err| format!("{} {}", path, err))? {
+                Some(record) => record,
+                None => break
+            };
+
+            if !header && columns.is_empty() {
+                columns = (1..=fields.len()).map(|index| format!("${}", index)).collect();
+            }
+
+            if fields.len() != columns.len() {
+                return Err(format!("{} line {}: expected {} fields, found {}", path, line, columns.len(), fields.len()).into());
+            }
+
+            records.push(fields);
+        }
+
+        let types: Vec<Type> = (0..columns.len())
+            .map(|column| Type::infer(records.iter().map(|record| record[column].as_str())))
+            .collect();
+
+        let rows = records.into_iter()
+            .map(|record| record.into_iter().zip(&types).map(|(field, kind)| kind.parse(field)).collect())
+            .collect();
+
+        return state.set_data(columns, rows);
+    }
+}
+
+impl Inputcsv {
+    // A single character, or None for an empty string
+    fn character(name: &str, value: &str) -> Result<Option<char>, Box<dyn Error>> {
+        let mut chars = value.chars();
+        return match (chars.next(), chars.next()) {
+            (c, None) => Ok(c),
+            _ => Err(format!("Option {} expects a single character, got {}", name, value).into())
+        };
+    }
+}
+
+enum Type {
+    Int,
+    Float,
+    Bool,
+    Timestamp,
+    String
+}
+
+impl Type {
+    // The first type every non-empty field can be read as
+    fn infer<'a>(fields: impl Iterator<Item = &'a str> + Clone) -> Self {
+        let mut fields = fields.filter(|field| !field.is_empty());
+
+        if fields.clone().all(|field| matches!(Self::number(field), Some(Value::Int(_)))) {
+            return Type::Int;
+        }
+        if fields.clone().all(|field| Self::number(field).is_some()) {
+            return Type::Float;
+        }
+        if fields.clone().all(|field| field.eq_ignore_ascii_case("true") || field.eq_ignore_ascii_case("false")) {
+            return Type::Bool;
+        }
+        if fields.all(|field| Value::parse_number(field).is_none() && time::parse_timestamp(field).is_some()) {
+            return Type::Timestamp;
+        }
+        return Type::String;
+    }
+
+    // The number in the field, unless reading it would change the text, as for 007 or +5
+    fn number(field: &str) -> Option<Value> {
+        let digits = field.strip_prefix('-').unwrap_or(field);
+        let leading_zero = digits.len() > 1 && digits.starts_with('0') && digits[1..].starts_with(|c: char| c.is_ascii_digit());
+        if leading_zero || !digits.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        return Value::parse_number(field);
+    }
+
+    fn parse(&self, field: String) -> Value {
+        if field.is_empty() {
+            return Value::Null;
+        }
+
+        return match self {
+            Type::Int | Type::Float => match Self::number(&field) {
+                Some(Value::Int(value)) if matches!(self, Type::Float) => Value::Float(value as f64),
+                Some(number) => number,
+                None => Value::String(field)
+            },
+            Type::Bool => Value::Bool(field.eq_ignore_ascii_case("true")),
+            Type::Timestamp => time::parse_timestamp(&field).map(Value::Timestamp).unwrap_or(Value::String(field)),
+            Type::String => Value::String(field)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{interpreter::{column, run}, output::Table, value::Value};
+
+    // Tests run in parallel, so every file gets its own name
+    fn read(name: &str, contents: &str, options: &str) -> Result<Table, Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("scrap-{}-{}.csv", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+
+        let table = run(&format!("| inputcsv path=\"{}\" {}", path.display(), options));
+        fs::remove_file(&path).unwrap();
+        return table;
+    }
+
+    #[test]
+    fn gives_every_column_a_type() {
+        let table = read("types", "n,x,ok,t,s,e\n1,1.5,true,2024-03-01,007,\n2,2,FALSE,2024-03-01T12:00:00Z,inf,\n", "").unwrap();
+        assert_eq!(table.columns(), ["n", "x", "ok", "t", "s", "e"]);
+        assert_eq!(column(&table, "n"), [Value::Int(1), Value::Int(2)]);
+        assert_eq!(column(&table, "x"), [Value::Float(1.5), Value::Float(2.0)]);
+        assert_eq!(column(&table, "ok"), [Value::Bool(true), Value::Bool(false)]);
+        assert_eq!(column(&table, "t"), [Value::Timestamp(1_709_251_200_000), Value::Timestamp(1_709_294_400_000)]);
+        assert_eq!(column(&table, "s"), [Value::from("007"), Value::from("inf")]);
+        assert_eq!(column(&table, "e"), [Value::Null, Value::Null]);
+    }
+
+    #[test]
+    fn reads_files_without_a_header() {
+        let table = read("tabs", "a\tb\nc\td\ne\tf\n", "header=false delim=\"\\t\" max_rows=2").unwrap();
+        assert_eq!(table.columns(), ["$1", "$2"]);
+        assert_eq!(column(&table, "$2"), [Value::from("b"), Value::from("d")]);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let err = read("fields", "a,b\n1,2\n3\n", "").err().unwrap().to_string();
+        assert!(err.ends_with("line 3: expected 2 fields, found 1"), "{}", err);
+        assert!(read("header", "a,a\n", "").err().unwrap().to_string().ends_with("line 1: column a appears twice in the header"));
+        assert!(read("delim", "a\n", "delim=\"\\\"\"").is_err());
+        assert!(run("| inputcsv path=\"/nonexistent/scrap.csv\"").is_err());
+    }
+    #[test]
+    fn keeps_numbers_that_would_change_as_text() {
+        let table = read("zeros", "zip,id,n,x\n01234,+5,0,0.5\n12345,7,-10,1e3\n", "").unwrap();
+        assert_eq!(column(&table, "zip"), [Value::from("01234"), Value::from("12345")]);
+        assert_eq!(column(&table, "id"), [Value::from("+5"), Value::from("7")]);
+        assert_eq!(column(&table, "n"), [Value::Int(0), Value::Int(-10)]);
+        assert_eq!(column(&table, "x"), [Value::Float(0.5), Value::Float(1000.0)]);
+    }
+}
@@ -0,0 +1,136 @@
+#![allow(clippy::needless_return)]
+
+use std::{error::Error, fs};
+
+use crate::{command::{option, parse_count, CommandExecutor}, json, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Inputjson;
+
+// Replaces the table with the objects of a JSON file, one row per object.
+// The file holds objects one after another (as in NDJSON, one per line) or a single array of objects.
+// Columns are every field found in any object, in the order they are first seen, missing fields are null.
+// Examples:
+// | inputjson path="events.ndjson"
+// | inputjson path="events.json" max_rows=100
+impl CommandExecutor for Inputjson {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut path: Option<&String> = None;
+        let mut max_rows: Option<usize> = None;
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    return Err("Usage: inputjson path=<string> [max_rows=<number>]".into());
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "path" => path = Some(value),
+                    "max_rows" => max_rows = Some(parse_count(name, value)?),
+                    _ => return Err(format!("Unknown option {} for inputjson", name).into())
+                }
+            }
+        }
+
+        let path = path.ok_or("Option path is required for inputjson")?;
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read {}: {}", path, err))?;
+
+        // Objects with the byte offset they start at, the line is only worked out for an error
+        let mut objects: Vec<(usize, serde_json::Value)> = Vec::new();
+        let mut stream = serde_json::Deserializer::from_str(&text).into_iter::<serde_json::Value>();
+
+        while max_rows.is_none_or(|max_rows| objects.len() < max_rows) {
+            // Where the value starts, the stream is past its end once it's read
+            let offset = json::skip_whitespace(&text, stream.byte_offset());
+            let value = match stream.next() {
+                Some(Ok(value)) => value,
+                Some(Err(err)) => return Err(json::error(path, &err).into()),
+                None => break
+            };
+
+            match value {
+                serde_json::Value::Array(values) if objects.is_empty() => objects.extend(values.into_iter().map(|value| (offset, value))),
+                value => objects.push((offset, value))
+            }
+        }
+
+        let mut columns: Vec<String> = Vec::new();
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+
+        for (offset, object) in objects.into_iter().take(max_rows.unwrap_or(usize::MAX)) {
+            if !object.is_object() {
+                return Err(format!("{} line {}: expected an object, got {}", path, json::line_at(&text, offset), object).into());
+            }
+
+            let mut fields: Vec<(String, Value)> = Vec::new();
+            json::flatten(&object, "", &mut fields);
+
+            let mut row = vec![Value::Null; columns.len()];
+            for (name, value) in fields {
+                match columns.iter().position(|column| *column == name) {
+                    Some(index) => row[index] = value,
+                    None => {
+                        columns.push(name);
+                        row.push(value);
+                    }
+                }
+            }
+            rows.push(row);
+        }
+
+        // Rows read before a column was first seen don't have it yet
+        for row in &mut rows {
+            row.resize(columns.len(), Value::Null);
+        }
+
+        return state.set_data(columns, rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{interpreter::{column, run}, value::Value};
+
+    #[test]
+    fn reads_objects_into_rows() {
+        let path = std::env::temp_dir().join(format!("scrap-inputjson-rows-{}.json", std::process::id()));
+        let read = |contents: &str, options: &str| {
+            fs::write(&path, contents).unwrap();
+            return run(&format!("| inputjson path=\"{}\" {}", path.display(), options)).unwrap();
+        };
+
+        let table = read("{\"a\": 1, \"user\": {\"id\": 7}}\n{\"b\": [1, 2], \"a\": null}\n", "");
+        assert_eq!(table.columns(), ["a", "user.id", "b"]);
+        assert_eq!(column(&table, "user.id"), [Value::Int(7), Value::Null]);
+        assert_eq!(column(&table, "b"), [Value::Null, Value::Multivalue(vec![Value::Int(1), Value::Int(2)])]);
+
+        let table = read("[{\"a\": 1}, {\"a\": 2}, {\"a\": 3}]", "max_rows=2");
+        assert_eq!(column(&table, "a"), [Value::Int(1), Value::Int(2)]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reports_the_line_an_object_starts_on() {
+        let path = std::env::temp_dir().join(format!("scrap-inputjson-{}.ndjson", std::process::id()));
+        fs::write(&path, "{\"a\": 1}\n\n{\n  \"a\": 2\n}\n[\n  1\n]\n").unwrap();
+
+        let err = run(&format!("| inputjson path=\"{}\"", path.display())).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.ends_with("line 6: expected an object, got [1]"), "{}", err);
+    }
+    #[test]
+    fn reads_a_large_file() {
+        let path = std::env::temp_dir().join(format!("scrap-inputjson-large-{}.ndjson", std::process::id()));
+        let text: String = (1..=50_000).map(|n| format!("{{\"n\": {}, \"level\": \"info\"}}\n", n)).collect();
+        fs::write(&path, text).unwrap();
+
+        let table = run(&format!("| inputjson path=\"{}\"", path.display())).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(table.row_count(), 50_000);
+        assert_eq!(column(&table, "n").last(), Some(&Value::Int(50_000)));
+    }
+}
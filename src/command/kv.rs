@@ -0,0 +1,178 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{option, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Kv;
+
+// Extracts key=value pairs from the text of a column into a column per key.
+// Values may be quoted to hold delimiters, and a key seen more than once in a row gives a multivalue.
+// Rows without a key keep their value in its column.
+// Examples:
+// | kv
+// | kv field=message pairdelim="," kvdelim=":" prefix="kv_"
+impl CommandExecutor for Kv {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut field = String::from("_raw");
+        let mut pair_delimiters: Vec<char> = vec![' '];
+        let mut kv_delimiters: Vec<char> = vec!['='];
+        let mut prefix = String::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    return Err("Usage: kv [field=<field>] [pairdelim=<string>] [kvdelim=<string>] [prefix=<string>]".into());
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "field" => field = value.clone(),
+                    "pairdelim" => pair_delimiters = Self::delimiters(name, value)?,
+                    "kvdelim" => kv_delimiters = Self::delimiters(name, value)?,
+                    "prefix" => prefix = value.clone(),
+                    _ => return Err(format!("Unknown option {} for kv", name).into())
+                }
+            }
+        }
+
+        if pair_delimiters.iter().any(|c| kv_delimiters.contains(c)) {
+            return Err("Options pairdelim and kvdelim can't share a character".into());
+        }
+
+        let source = state.get_column(&field)
+            .map_err(|_| format!("Cannot kv {}: no such column", field))?;
+
+        let extracted: Vec<Vec<(String, Value)>> = state.rows().iter()
+            .map(|row| match &row[source] {
+                Value::Null => Vec::new(),
+                value => Self::pairs(&value.to_string(), &pair_delimiters, &kv_delimiters)
+            })
+            .collect();
+
+        for (index, pairs) in extracted.into_iter().enumerate() {
+            for (key, value) in pairs {
+                let column = state.get_or_create_column(&format!("{}{}", prefix, key))?;
+                state.set_cell(column, index, value)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+impl Kv {
+    // Every character of the option is a delimiter
+    fn delimiters(name: &str, value: &str) -> Result<Vec<char>, Box<dyn Error>> {
+        let delimiters: Vec<char> = value.chars().collect();
+        if delimiters.is_empty() || delimiters.contains(&'"') {
+            return Err(format!("Option {} expects characters other than a quote, got \"{}\"", name, value).into());
+        }
+        return Ok(delimiters);
+    }
+
+    // The pairs of the text in order of their keys' first appearance, values of repeated keys are collected into a multivalue.
+    // Unquoted values that are numbers become numbers and empty ones null, quoted values stay text.
+    fn pairs(text: &str, pair_delimiters: &[char], kv_delimiters: &[char]) -> Vec<(String, Value)> {
+        let mut pairs: Vec<(String, Vec<Value>)> = Vec::new();
+        let mut chars = text.chars().peekable();
+
+        while chars.peek().is_some() {
+            while chars.next_if(|c| pair_delimiters.contains(c)).is_some() {}
+
+            let mut key = String::new();
+            while let Some(c) = chars.next_if(|c| !pair_delimiters.contains(c) && !kv_delimiters.contains(c)) {
+                key.push(c);
+            }
+
+            // Text without a key value delimiter isn't a pair
+            if chars.next_if(|c| kv_delimiters.contains(c)).is_none() {
+                continue;
+            }
+
+            let value = if chars.next_if_eq(&'"').is_some() {
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if chars.peek().is_some_and(|next| *next == '"' || *next == '\\') => value.push(chars.next().unwrap()),
+                        c => value.push(c)
+                    }
+                }
+                // Anything between the closing quote and the next pair is dropped
+                while chars.next_if(|c| !pair_delimiters.contains(c)).is_some() {}
+                Value::String(value)
+            } else {
+                let mut value = String::new();
+                while let Some(c) = chars.next_if(|c| !pair_delimiters.contains(c)) {
+                    value.push(c);
+                }
+                match Value::parse_number(&value) {
+                    _ if value.is_empty() => Value::Null,
+                    Some(number) => number,
+                    None => Value::String(value)
+                }
+            };
+
+            let key = key.trim();
+            if key.is_empty() {
+                continue;
+            }
+
+            match pairs.iter_mut().find(|(existing, _)| existing == key) {
+                Some((_, values)) => values.push(value),
+                None => pairs.push((key.to_string(), vec![value]))
+            }
+        }
+
+        return pairs.into_iter()
+            .map(|(key, mut values)| match values.len() {
+                1 => (key, values.remove(0)),
+                _ => (key, Value::Multivalue(values))
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{column, run};
+
+    fn pairs(text: &str) -> Vec<(String, Value)> {
+        return Kv::pairs(text, &[' '], &['=']);
+    }
+
+    #[test]
+    fn reads_pairs_and_typed_values() {
+        assert_eq!(pairs(r#"user=ann id=7 ratio=0.5 empty= lone msg="a b \"c\"" x=inf"#), [
+            ("user".to_string(), Value::from("ann")),
+            ("id".to_string(), Value::Int(7)),
+            ("ratio".to_string(), Value::Float(0.5)),
+            ("empty".to_string(), Value::Null),
+            ("msg".to_string(), Value::from("a b \"c\"")),
+            ("x".to_string(), Value::from("inf"))
+        ]);
+        assert_eq!(pairs("ip=1 ip=2 =3"), [("ip".to_string(), Value::Multivalue(vec![Value::Int(1), Value::Int(2)]))]);
+        assert_eq!(Kv::pairs("a:1, b:\"2\"", &[',', ' '], &[':']), [
+            ("a".to_string(), Value::Int(1)),
+            ("b".to_string(), Value::from("2"))
+        ]);
+    }
+
+    #[test]
+    fn writes_a_column_per_key() {
+        let table = run(r#"| generate count=2 | eval message = if($rowcount == 1, "a=1 b=x", "a=2") | eval kv_b = "old" | kv field=message prefix="kv_""#).unwrap();
+        assert_eq!(column(&table, "kv_a"), [Value::Int(1), Value::Int(2)]);
+        assert_eq!(column(&table, "kv_b"), [Value::from("x"), Value::from("old")]);
+    }
+
+    #[test]
+    fn rejects_invalid_delimiters() {
+        let query = "| generate count=1 | eval m = \"a=1\"";
+        assert!(run(&format!("{} | kv field=m pairdelim=\"=\"", query)).is_err());
+        assert!(run(&format!("{} | kv field=m kvdelim=\"\\\"\"", query)).is_err());
+        assert!(run(&format!("{} | kv field=m kvdelim=\"\"", query)).is_err());
+        assert!(run(&format!("{} | kv", query)).is_err());
+    }
+}
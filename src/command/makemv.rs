@@ -0,0 +1,79 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, option, parse_bool, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Makemv;
+
+// Splits the text of every value of the given fields into a multivalue.
+// Empty parts are dropped unless allowempty is set, values that are already multivalues or null are kept.
+// Examples:
+// | makemv tags
+// | makemv delim="," tags, ips
+impl CommandExecutor for Makemv {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut delimiter = String::from(" ");
+        let mut allow_empty = false;
+        let mut fields: Vec<&String> = Vec::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    fields.push(field_name(node)?);
+                    continue;
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "delim" => delimiter = value.clone(),
+                    "allowempty" => allow_empty = parse_bool(name, value)?,
+                    _ => return Err(format!("Unknown option {} for makemv", name).into())
+                }
+            }
+        }
+
+        if fields.is_empty() {
+            return Err("Usage: makemv [delim=<string>] [allowempty=<bool>] <field>[, <field>...]".into());
+        }
+
+        if delimiter.is_empty() {
+            return Err("Option delim expects a non-empty string".into());
+        }
+
+        for field in fields {
+            let column = state.get_column(field)
+                .map_err(|_| format!("Cannot makemv {}: no such column", field))?;
+
+            state.map_column(column, |value| match value {
+                Value::Null | Value::Multivalue(_) => value.clone(),
+                value => {
+                    let values: Vec<Value> = value.to_string()
+                        .split(delimiter.as_str())
+                        .filter(|part| allow_empty || !part.is_empty())
+                        .map(Value::from)
+                        .collect();
+
+                    if values.is_empty() { Value::Null } else { Value::Multivalue(values) }
+                }
+            })?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    #[test]
+    fn splits_text_into_values() {
+        let table = run("| generate count=1 | eval a = \"x y  z\" | eval b = \"1,,2\" | makemv a | makemv delim=\",\" allowempty=true b").unwrap();
+        assert_eq!(column(&table, "a"), [Value::Multivalue(vec!["x".into(), "y".into(), "z".into()])]);
+        assert_eq!(column(&table, "b"), [Value::Multivalue(vec!["1".into(), "".into(), "2".into()])]);
+
+        assert!(run("| generate count=1 | eval a = 1 | makemv delim=\"\" a").is_err());
+        assert!(run("| generate count=1 | makemv x").is_err());
+    }
+}
@@ -1,76 +1,384 @@
-use std::{collections::HashMap, error::Error};
+#![allow(clippy::needless_return)]
 
-use crate::{output::Table, parser::{ParseNode, ParseNodeType}, scanner::{Token, TokenType}};
+use std::{collections::HashMap, error::Error, fs, io, marker::PhantomData, path::Path, process};
+
+use crate::{function::{self, Arguments}, output::Table, pattern, time, parser::{ParseNode, ParseNodeType}, scanner::TokenType, value::Value};
 
 pub mod generate;
 pub mod eval;
 pub mod r#where;
+pub mod rename;
+pub mod remove;
+pub mod top;
+pub mod stats;
+pub mod sort;
+pub mod head;
+pub mod tail;
+pub mod dedup;
+pub mod table;
+pub mod fields;
+pub mod rex;
+pub mod bin;
+pub mod makemv;
+pub mod mvexpand;
+pub mod nomv;
+pub mod inputcsv;
+pub mod outputcsv;
+pub mod outputjson;
+pub mod inputjson;
+pub mod spath;
+pub mod kv;
+
+// Returns the name held by a field argument - an identifier, special identifier or a quoted string
+pub fn field_name(node: &ParseNode) -> Result<&String, Box<dyn Error>> {
+    let token = node
+        .expect_type(ParseNodeType::Value)?
+        .token.as_ref().ok_or("Token is empty")?;
+
+    return match token.token_type {
+        TokenType::Identifier |
+        TokenType::SpecialIdentifier |
+        TokenType::String => Ok(token.value.as_ref().ok_or("Token is empty")?),
+        _ => Err(format!("Expected a field name, got {:} at line {} offset {}", token, token.line, token.offset).into())
+    };
+}
+
+// Returns the name and value of a name=value option
+pub fn option(node: &ParseNode) -> Result<(&String, &String), Box<dyn Error>> {
+    let node = node.expect_type(ParseNodeType::Assignment)?;
+    let name = node.child(0).and_then(|c| c.token_value()).ok_or("Option is missing a name")?;
+    let value = node.child(1).and_then(|c| c.token_value())
+        .ok_or(format!("Option {} expects a single value", name))?;
+
+    return Ok((name, value));
+}
+
+pub fn parse_bool(name: &str, value: &str) -> Result<bool, Box<dyn Error>> {
+    return match value {
+        "true" | "t" | "1" => Ok(true),
+        "false" | "f" | "0" => Ok(false),
+        _ => Err(format!("Option {} expects true or false, got {}", name, value).into())
+    };
+}
+
+pub fn parse_count(name: &str, value: &str) -> Result<usize, Box<dyn Error>> {
+    return value.parse::<usize>()
+        .map_err(|_| format!("Option {} expects a whole number, got {}", name, value).into());
+}
+
+// Replaces the contents of a file in one step, by writing a temporary file next to it and renaming it,
+// so readers never see a half written file
+pub fn replace_file(path: &str, contents: &str) -> Result<(), Box<dyn Error>> {
+    let target = Path::new(path);
+    let name = target.file_name().ok_or(format!("Cannot write {}: not a file name", path))?;
+    let temporary = target.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+
+    let result = fs::write(&temporary, contents).and_then(|_| fs::rename(&temporary, target));
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temporary);
+        return Err(format!("Cannot write {}: {}", path, err).into());
+    }
+
+    return Ok(());
+}
+
+// The contents of a file, or None when it doesn't exist
+pub fn read_existing_file(path: &str) -> Result<Option<String>, Box<dyn Error>> {
+    return match fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("Cannot read {}: {}", path, err).into())
+    };
+}
 
 // TODO: Clean up this trait
 pub trait CommandExecutor {
     fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>>;
 
-    // Input: Value or Expression node, hashmap of variables.
-    // Output: An evaluated Value node.
-    fn evaluate(val_or_expr: &ParseNode, env: &HashMap<String, String>) -> Result<ParseNode, Box<dyn Error>> {
+    // Input: Condition, Comparison or any node evaluating to a bool, hashmap of variables.
+    // Output: Whether the condition holds, 'and' and 'or' stop as soon as the result is known.
+    fn test(condition: &ParseNode, env: &HashMap<String, Value>) -> Result<bool, Box<dyn Error>> {
+        if condition.variant == ParseNodeType::Comparison {
+            return Self::compare(condition, env);
+        }
+
+        // Anything else must be a bool, e.g. a function call, null counts as false like in comparisons
+        if condition.variant != ParseNodeType::Condition {
+            return match Self::evaluate(condition, env)? {
+                Value::Bool(result) => Ok(result),
+                Value::Null => Ok(false),
+                value => Err(format!("Expected a bool condition, got {} \"{}\"", value.type_name(), value).into())
+            };
+        }
+
+        let children = condition.expect_type(ParseNodeType::Condition)?.children();
+
+        return match children {
+            [operator, operand] if operator.token_type() == TokenType::Not => Ok(!Self::test(operand, env)?),
+            [lvalue, operator, rvalue] => match operator.token_type() {
+                TokenType::And => Ok(Self::test(lvalue, env)? && Self::test(rvalue, env)?),
+                TokenType::Or => Ok(Self::test(lvalue, env)? || Self::test(rvalue, env)?),
+                _ => Err("Unknown logical operator".into())
+            },
+            _ => Err("Malformed condition".into())
+        };
+    }
+
+    // Input: Comparison node, hashmap of variables.
+    // Output: Whether the comparison holds.
+    fn compare(comparison: &ParseNode, env: &HashMap<String, Value>) -> Result<bool, Box<dyn Error>> {
+        let comparison = comparison.expect_type(ParseNodeType::Comparison)?;
+
+        // Value or expression
+        let lval = comparison
+            .child(0).ok_or("Comparison is missing a value")?;
+
+        // Must be operator
+        let operator = comparison
+            .child(1).ok_or("Comparison is missing an operator")?
+            .expect_type(ParseNodeType::ComparisonOperator)?
+            .token_type();
+
+        let lval_evaluated = Self::evaluate(lval, env)?;
+
+        // A comparison on a multivalue holds when it holds for any of its values
+        let lvals = match &lval_evaluated {
+            Value::Multivalue(values) => values.as_slice(),
+            value => std::slice::from_ref(value)
+        };
+
+        // x in (a, b, ...) holds when x equals one of the values, the rest are skipped after a match
+        if operator == TokenType::In {
+            for rval in &comparison.children()[2..] {
+                let rval_evaluated = Self::evaluate(rval, env)?;
+                if lvals.iter().any(|lval| !lval.is_null() && lval.compare(&rval_evaluated).is_some_and(|ordering| ordering.is_eq())) {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+
+        // Value or expression
+        let rval = comparison
+            .child(2).ok_or("Comparison is missing a value")?;
+
+        let rval_evaluated = Self::evaluate(rval, env)?;
+
+        for lval in lvals {
+            if Self::holds(lval, &operator, &rval_evaluated)? {
+                return Ok(true);
+            }
+        }
+
+        return Ok(false);
+    }
+
+    fn holds(lval: &Value, operator: &TokenType, rval: &Value) -> Result<bool, Box<dyn Error>> {
+        // x =~ pattern holds when the pattern matches anywhere in the text of x
+        if matches!(operator, TokenType::Match | TokenType::NotMatch) {
+            if lval.is_null() || rval.is_null() {
+                return Ok(false);
+            }
+
+            let is_match = pattern::compile(&rval.to_string())?.is_match(&lval.to_string());
+            return Ok(is_match == (*operator == TokenType::Match));
+        }
+
+        // Comparisons involving null (or values that can't be compared) never hold
+        let ordering = match lval.compare(rval) {
+            Some(ordering) => ordering,
+            None => return Ok(false)
+        };
+
+        return Ok(match operator {
+            TokenType::Eeq => ordering.is_eq(),
+            TokenType::Neq => ordering.is_ne(),
+            TokenType::Gt  => ordering.is_gt(),
+            TokenType::Lt  => ordering.is_lt(),
+            TokenType::Gte => ordering.is_ge(),
+            TokenType::Lte => ordering.is_le(),
+            _ => false
+        });
+    }
+
+    // Input: Value, Expression, Call, Comparison or Condition node, hashmap of variables.
+    // Output: The evaluated value, conditions evaluate to a bool.
+    fn evaluate(val_or_expr: &ParseNode, env: &HashMap<String, Value>) -> Result<Value, Box<dyn Error>> {
         
         // When we have a Value of type Identifier or SpecialIdentifier:
-        // - We just subsitute the variable into a value node
+        // - We just subsitute the variable, unknown variables are null
         if matches!(val_or_expr.variant, ParseNodeType::Value) {
             let token = val_or_expr.token.as_ref().unwrap();
-            let mut token_value = token.value.clone().unwrap();
-            if  matches!(token.token_type, TokenType::Identifier) ||
-                matches!(token.token_type, TokenType::SpecialIdentifier) {
-                let val = token.value.as_ref().unwrap();
-                token_value = env.get(val).unwrap().clone();
-            }
-            // Determine the type of substituted value - Naively parse int and fallback to string if fails
-            let token_type = if token_value.parse::<usize>().is_ok() { TokenType::Number } else { TokenType::String };
-            
-            return Ok(ParseNode { variant: ParseNodeType::Value, children: None, token: Some(Token { token_type: token_type, value: Some(token_value), line: 0, offset: 0}) });
+            let token_value = token.value.clone().unwrap();
+
+            return match token.token_type {
+                TokenType::Identifier |
+                TokenType::SpecialIdentifier => Ok(env.get(&token_value).cloned().unwrap_or(Value::Null)),
+                TokenType::Number => Value::parse_number(&token_value)
+                    .ok_or(format!("Invalid number {} at line {} offset {}", token_value, token.line, token.offset).into()),
+                TokenType::Duration => time::parse_duration(&token_value)
+                    .ok_or(format!("Invalid duration {} at line {} offset {}", token_value, token.line, token.offset).into()),
+                TokenType::String => Ok(Value::String(token_value)),
+                TokenType::Boolean => Ok(Value::Bool(token_value == "true")),
+                TokenType::Null => Ok(Value::Null),
+                _ => Err(format!("Unexpected token {:} at line {} offset {}", token, token.line, token.offset).into())
+            };
         } else if matches!(val_or_expr.variant, ParseNodeType::Expression) {
             let children = val_or_expr.children();
-            // Left node is always a Value
-            let lvalue = &children[0];
+
+            // Unary expression, e.g. -x
+            if let [operator, operand] = children {
+                let operand = Self::evaluate(operand, env)?;
+                let operator = operator.expect_type(ParseNodeType::ArithmeticOperator)?;
+
+                return match operator.token_type() {
+                    TokenType::Sub => operand.negate(),
+                    // x - 0 keeps the number as is, but still rejects values that aren't numbers
+                    TokenType::Add => operand.sub(&Value::Int(0)),
+                    _ => Err("Not implemented".into())
+                };
+            }
+
+            // Left node is either a Value or an Expression
+            let lvalue = children.first().ok_or("Expression is missing a value")?;
 
             // It's possible that the value will be an identifier
-            let lvalue = &Self::evaluate(lvalue, &env)?;
+            let lvalue = Self::evaluate(lvalue, env)?;
             
             // Middle node is always an operator
-            let operator = children[1]
+            let operator = children.get(1).ok_or("Expression is missing an operator")?
                 .expect_type(ParseNodeType::ArithmeticOperator)?;
             
-            // This will be either a Value or Expression
-            let rvalue: &ParseNode = &children[2];
-
-            // If rvalue is an Expression, recursively call and change the node
-            let rvalue = &Self::evaluate(rvalue, &env)?;
+            // Right node is either a Value or an Expression
+            let rvalue = children.get(2).ok_or("Expression is missing a value")?;
+            let rvalue = Self::evaluate(rvalue, env)?;
 
             // Calculate expression
-            return if operator.expect_token_type(TokenType::Add).is_ok() {
-                // Ok(String::from("LOL"))
-                let ltoken = lvalue.token.as_ref().unwrap();
-                let rtoken = rvalue.token.as_ref().unwrap();
-
-                let mut res = String::new();
-
-                if  matches!(ltoken.token_type, TokenType::Number) &&
-                    matches!(rtoken.token_type, TokenType::Number) {
-                    
-                    let lnum: usize = lvalue.token_value().unwrap().parse()?;
-                    let rnum: usize = rvalue.token_value().unwrap().parse()?;
-                    res.push_str(&(lnum + rnum).to_string());
-                    Ok(ParseNode { variant: ParseNodeType::Value, children: None, token: Some(Token { token_type: TokenType::Number, value: Some(res), line: 0, offset: 0 }) })
-                } else {
-                    res.push_str(lvalue.token_value().unwrap());
-                    res.push_str(rvalue.token_value().unwrap());
-                    Ok(ParseNode { variant: ParseNodeType::Value, children: None, token: Some(Token { token_type: TokenType::String, value: Some(res), line: 0, offset: 0 }) })
-                }
-            } else {
-                Err("Not implemented".into())
+            return match operator.token_type() {
+                TokenType::Add => lvalue.add(&rvalue),
+                TokenType::Sub => lvalue.sub(&rvalue),
+                TokenType::Mul => lvalue.mul(&rvalue),
+                TokenType::Div => lvalue.div(&rvalue),
+                TokenType::Mod => lvalue.rem(&rvalue),
+                TokenType::Pow => lvalue.pow(&rvalue),
+                _ => Err("Not implemented".into())
             };
+        } else if matches!(val_or_expr.variant, ParseNodeType::Call) {
+            let token = val_or_expr.token.as_ref().ok_or("Function is missing a name")?;
+            let name = token.value.as_ref().ok_or("Function is missing a name")?;
+
+            let function = function::find(name)
+                .ok_or(format!("Unknown function {} at line {} offset {}", name, token.line, token.offset))?;
+
+            // Arguments are evaluated when the function asks for them, their errors are passed on as they are
+            let mut arguments = CallArguments::<Self> { nodes: val_or_expr.children(), env, error: None, executor: PhantomData };
+            let result = function.call(&mut arguments);
+
+            if let Some(err) = arguments.error {
+                return Err(err);
+            }
+
+            return result
+                .map_err(|err| format!("{} at line {} offset {}", err, token.line, token.offset).into());
+        } else if matches!(val_or_expr.variant, ParseNodeType::Comparison | ParseNodeType::Condition) {
+            return Ok(Value::Bool(Self::test(val_or_expr, env)?));
         } else {
             return Err("Not an expression nor a value".into());
         }
     }
-}
\ No newline at end of file
+}
+
+// The arguments of a function call, evaluated on the row of the executor
+struct CallArguments<'a, E: ?Sized> {
+    nodes: &'a [ParseNode],
+    env: &'a HashMap<String, Value>,
+    // The first error raised by an argument
+    error: Option<Box<dyn Error>>,
+    executor: PhantomData<E>
+}
+
+impl<E: CommandExecutor + ?Sized> CallArguments<'_, E> {
+    fn evaluate_in(&mut self, index: usize, env: &HashMap<String, Value>) -> Result<Value, Box<dyn Error>> {
+        return E::evaluate(&self.nodes[index], env).map_err(|err| {
+            let message = err.to_string();
+            self.error.get_or_insert(err);
+            return message.into();
+        });
+    }
+
+    fn collect_fields(node: &ParseNode, fields: &mut Vec<String>) {
+        let is_field = node.variant == ParseNodeType::Value
+            && matches!(node.token_type(), TokenType::Identifier | TokenType::SpecialIdentifier);
+
+        if let Some(name) = node.token_value().filter(|name| is_field && !fields.contains(name)) {
+            fields.push(name.clone());
+        }
+
+        for child in node.children() {
+            Self::collect_fields(child, fields);
+        }
+    }
+}
+
+impl<E: CommandExecutor + ?Sized> Arguments for CallArguments<'_, E> {
+    fn count(&self) -> usize {
+        return self.nodes.len();
+    }
+
+    fn evaluate(&mut self, index: usize) -> Result<Value, Box<dyn Error>> {
+        return self.evaluate_in(index, self.env);
+    }
+
+    fn evaluate_with(&mut self, index: usize, field: &str, value: Value) -> Result<Value, Box<dyn Error>> {
+        let mut env = self.env.clone();
+        env.insert(field.to_string(), value);
+        return self.evaluate_in(index, &env);
+    }
+
+    fn fields(&self, index: usize) -> Vec<String> {
+        let mut fields = Vec::new();
+        Self::collect_fields(&self.nodes[index], &mut fields);
+        return fields;
+    }
+
+    fn field(&self, name: &str) -> Value {
+        return self.env.get(name).cloned().unwrap_or(Value::Null);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter, value::Value};
+
+    fn evaluate(expression: &str) -> Value {
+        return interpreter::evaluate(expression).unwrap();
+    }
+
+    #[test]
+    fn evaluates_floats_and_negative_numbers() {
+        assert_eq!(evaluate("-1.5"), Value::Float(-1.5));
+        assert_eq!(evaluate("2 - -3"), Value::Int(5));
+        assert_eq!(evaluate("1.5 + 1"), Value::Float(2.5));
+        assert_eq!(evaluate("2.5e2"), Value::Float(250.0));
+        assert_eq!(evaluate("-9223372036854775808"), Value::Int(i64::MIN));
+        assert_eq!(evaluate("9223372036854775808"), Value::Float(9223372036854775808.0));
+        assert_eq!(evaluate("\"-4\" * 2"), Value::Int(-8));
+    }
+
+    #[test]
+    fn evaluates_arithmetic_by_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4"), Value::Int(14));
+        assert_eq!(evaluate("(2 + 3) * 4"), Value::Int(20));
+        assert_eq!(evaluate("2 ** 3 ** 2"), Value::Int(512));
+        assert_eq!(evaluate("-2 ** 2"), Value::Int(-4));
+        assert_eq!(evaluate("(-2) ** 2"), Value::Int(4));
+        assert_eq!(evaluate("-(2) ** 2"), Value::Int(-4));
+        assert_eq!(evaluate("2 ** -1"), Value::Float(0.5));
+        assert_eq!(evaluate("10 - 4 - 3"), Value::Int(3));
+        assert_eq!(evaluate("17 % 5 * 2"), Value::Int(4));
+        assert_eq!(evaluate("7 / 2"), Value::Float(3.5));
+        assert_eq!(evaluate("1 / 0"), Value::Null);
+        assert_eq!(evaluate("$rowcount * (1 + 1)"), Value::Int(2));
+        assert!(interpreter::evaluate("\"a\" * 2").is_err());
+    }
+}
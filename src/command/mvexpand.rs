@@ -0,0 +1,84 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, option, parse_count, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Mvexpand;
+
+// Replaces every row by one row per value of the multivalue in the given field, keeping the other columns.
+// Rows without a multivalue are kept as they are, limit keeps only the first values of every multivalue.
+// Examples:
+// | mvexpand tags
+// | mvexpand tags limit=2
+impl CommandExecutor for Mvexpand {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut limit: Option<usize> = None;
+        let mut fields: Vec<&String> = Vec::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    fields.push(field_name(node)?);
+                    continue;
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "limit" => limit = Some(parse_count(name, value)?),
+                    _ => return Err(format!("Unknown option {} for mvexpand", name).into())
+                }
+            }
+        }
+
+        let field = match fields.as_slice() {
+            [field] => *field,
+            _ => return Err("Usage: mvexpand <field> [limit=<number>]".into())
+        };
+
+        let column = state.get_column(field)
+            .map_err(|_| format!("Cannot mvexpand {}: no such column", field))?;
+
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+
+        for row in state.rows() {
+            let values = match &row[column] {
+                Value::Multivalue(values) if !values.is_empty() => values,
+                Value::Multivalue(_) => &vec![Value::Null],
+                _ => {
+                    rows.push(row.clone());
+                    continue;
+                }
+            };
+
+            for value in values.iter().take(limit.unwrap_or(usize::MAX)) {
+                let mut expanded = row.clone();
+                expanded[column] = value.clone();
+                rows.push(expanded);
+            }
+        }
+
+        state.set_data(state.columns().to_vec(), rows)?;
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    #[test]
+    fn makes_a_row_for_every_value() {
+        let query = "| generate count=2 | eval id = $rowcount | eval tags = if($rowcount == 1, split(\"a,b,c\", \",\"), \"d\")";
+
+        let table = run(&format!("{} | mvexpand tags", query)).unwrap();
+        assert_eq!(column(&table, "id"), [Value::Int(1), Value::Int(1), Value::Int(1), Value::Int(2)]);
+        assert_eq!(column(&table, "tags"), [Value::from("a"), Value::from("b"), Value::from("c"), Value::from("d")]);
+
+        let table = run(&format!("{} | mvexpand tags limit=2", query)).unwrap();
+        assert_eq!(column(&table, "tags"), [Value::from("a"), Value::from("b"), Value::from("d")]);
+
+        assert!(run(&format!("{} | mvexpand tags, id", query)).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, option, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Nomv;
+
+// Joins the values of multivalues in the given fields into a single string
+// Examples:
+// | nomv tags
+// | nomv delim=";" tags, ips
+impl CommandExecutor for Nomv {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut delimiter = String::from(", ");
+        let mut fields: Vec<&String> = Vec::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    fields.push(field_name(node)?);
+                    continue;
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "delim" => delimiter = value.clone(),
+                    _ => return Err(format!("Unknown option {} for nomv", name).into())
+                }
+            }
+        }
+
+        if fields.is_empty() {
+            return Err("Usage: nomv [delim=<string>] <field>[, <field>...]".into());
+        }
+
+        for field in fields {
+            let column = state.get_column(field)
+                .map_err(|_| format!("Cannot nomv {}: no such column", field))?;
+
+            state.map_column(column, |value| match value {
+                Value::Multivalue(values) => Value::String(values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(&delimiter)),
+                value => value.clone()
+            })?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    #[test]
+    fn joins_values_into_text() {
+        let table = run("| generate count=1 | eval a = split(\"x,y\", \",\") | eval b = a | eval c = 5 | nomv a | nomv delim=\";\" b, c").unwrap();
+        assert_eq!(column(&table, "a"), [Value::from("x, y")]);
+        assert_eq!(column(&table, "b"), [Value::from("x;y")]);
+        assert_eq!(column(&table, "c"), [Value::Int(5)]);
+    }
+}
@@ -0,0 +1,115 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{option, parse_bool, read_existing_file, replace_file, CommandExecutor}, csv::{self, Format, Reader}, output::Table, parser::{ParseNode, ParseNodeType}};
+
+pub struct Outputcsv;
+
+// Writes the table to a CSV file and passes it on unchanged.
+// With append, the rows are added to the end of an existing file, whose header must match the columns.
+// Examples:
+// | outputcsv path="out.csv"
+// | outputcsv path="out.csv" append=true
+impl CommandExecutor for Outputcsv {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut path: Option<&String> = None;
+        let mut append = false;
+        let mut header = true;
+        let mut format = Format { delimiter: ',', quote: Some('"') };
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    return Err("Usage: outputcsv path=<string> [append=<bool>] [header=<bool>] [delim=<char>]".into());
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "path" => path = Some(value),
+                    "append" => append = parse_bool(name, value)?,
+                    "header" => header = parse_bool(name, value)?,
+                    "delim" => format.delimiter = Self::delimiter(value)?,
+                    _ => return Err(format!("Unknown option {} for outputcsv", name).into())
+                }
+            }
+        }
+
+        let path = path.ok_or("Option path is required for outputcsv")?;
+
+        let mut contents = String::new();
+        let existing = if append { read_existing_file(path)? } else { None };
+
+        match existing.filter(|existing| !existing.trim().is_empty()) {
+            Some(existing) => {
+                if header {
+                    let columns = Reader::new(&existing, &format).next_record()
+                        .map_err(|err| format!("{} {}", path, err))?
+                        .map(|record| record.fields)
+                        .unwrap_or_default();
+
+                    if columns != state.columns() {
+                        return Err(format!("Cannot append to {}: its columns {} don't match {}", path, columns.join(", "), state.columns().join(", ")).into());
+                    }
+                }
+
+                contents.push_str(&existing);
+                if !existing.ends_with('\n') {
+                    contents.push('\n');
+                }
+            },
+            None => if header {
+                contents.push_str(&csv::write_record(state.columns(), &format));
+            }
+        }
+
+        for row in state.rows() {
+            let fields: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+            contents.push_str(&csv::write_record(&fields, &format));
+        }
+
+        return replace_file(path, &contents);
+    }
+}
+
+impl Outputcsv {
+    // A single character other than a quote or line break
+    fn delimiter(value: &str) -> Result<char, Box<dyn Error>> {
+        let mut chars = value.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) if !matches!(c, '"' | '\n' | '\r') => Ok(c),
+            _ => Err(format!("Option delim expects a single character other than a quote, got {}", value).into())
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::interpreter::run;
+
+    #[test]
+    fn writes_and_appends_rows() {
+        let path = std::env::temp_dir().join(format!("scrap-outputcsv-{}.csv", std::process::id()));
+        let query = |command: &str| run(&format!("| generate count=2 | eval n = $rowcount | eval text = \"a,b\" | {} path=\"{}\"", command, path.display()));
+
+        let table = query("outputcsv").unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "$1,n,text\n,1,\"a,b\"\n,2,\"a,b\"\n");
+
+        query("outputcsv append=true").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 5);
+
+        let err = run(&format!("| generate count=1 | outputcsv append=true path=\"{}\"", path.display())).err().unwrap().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.contains("its columns $1, n, text don't match $1"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        assert!(run("| generate count=1 | outputcsv").is_err());
+        assert!(run("| generate count=1 | outputcsv path=\"x.csv\" delim=\"\\\"\"").is_err());
+        assert!(run("| generate count=1 | outputcsv path=\"/nonexistent/dir/x.csv\"").is_err());
+    }
+}
@@ -0,0 +1,120 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{option, parse_bool, read_existing_file, replace_file, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}};
+
+pub struct Outputjson;
+
+// Writes the table to a JSON file, one object per row, and passes it on unchanged.
+// ndjson writes an object per line, array a single array holding every object.
+// With append, the rows are added to the end of an existing file.
+// Examples:
+// | outputjson path="out.ndjson"
+// | outputjson path="out.json" format=array append=true
+impl CommandExecutor for Outputjson {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut path: Option<&String> = None;
+        let mut append = false;
+        let mut array = false;
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    return Err("Usage: outputjson path=<string> [format=ndjson|array] [append=<bool>]".into());
+                }
+
+                let (name, value) = option(node)?;
+                match (name.as_str(), value.as_str()) {
+                    ("path", _) => path = Some(value),
+                    ("append", _) => append = parse_bool(name, value)?,
+                    ("format", "ndjson") => array = false,
+                    ("format", "array") => array = true,
+                    ("format", _) => return Err(format!("Option format expects ndjson or array, got {}", value).into()),
+                    _ => return Err(format!("Unknown option {} for outputjson", name).into())
+                }
+            }
+        }
+
+        let path = path.ok_or("Option path is required for outputjson")?;
+
+        let objects = state.rows().iter().map(|row| {
+            let object: serde_json::Map<String, serde_json::Value> = state.columns().iter()
+                .zip(row)
+                .map(|(column, value)| (column.clone(), serde_json::Value::from(value)))
+                .collect();
+            serde_json::Value::Object(object)
+        });
+
+        let existing = if append { read_existing_file(path)? } else { None };
+        let existing = existing.filter(|existing| !existing.trim().is_empty());
+
+        let contents = if array {
+            // The existing array is read back, so the file stays a single valid array
+            let mut values = match existing {
+                Some(existing) => match serde_json::from_str(&existing) {
+                    Ok(serde_json::Value::Array(values)) => values,
+                    Ok(_) => return Err(format!("Cannot append to {}: it doesn't hold a JSON array", path).into()),
+                    Err(err) => return Err(format!("Cannot append to {}: {}", path, err).into())
+                },
+                None => Vec::new()
+            };
+            values.extend(objects);
+
+            let lines: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            match lines.is_empty() {
+                true => String::from("[]\n"),
+                false => format!("[\n{}\n]\n", lines.join(",\n"))
+            }
+        } else {
+            let mut contents = existing.unwrap_or_default();
+            if !contents.is_empty() && !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+            for object in objects {
+                contents.push_str(&object.to_string());
+                contents.push('\n');
+            }
+            contents
+        };
+
+        return replace_file(path, &contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::interpreter::run;
+
+    fn write(name: &str, options: &str, rows: usize) -> String {
+        let path = std::env::temp_dir().join(format!("scrap-{}-{}.json", name, std::process::id()));
+        for _ in 0..2 {
+            run(&format!("| generate count={} | eval n = $rowcount | eval tags = split(\"a,b\", \",\") | outputjson path=\"{}\" append=true {}", rows, path.display(), options)).unwrap();
+        }
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        return contents;
+    }
+
+    #[test]
+    fn appends_objects_as_lines() {
+        let line = |n: usize| format!("{{\"$1\":null,\"n\":{},\"tags\":[\"a\",\"b\"]}}\n", n);
+        assert_eq!(write("outputjson-ndjson", "", 1), line(1) + &line(1));
+    }
+
+    #[test]
+    fn appends_objects_to_a_single_array() {
+        let values: serde_json::Value = serde_json::from_str(&write("outputjson-array", "format=array", 2)).unwrap();
+        let numbers: Vec<i64> = values.as_array().unwrap().iter().map(|value| value["n"].as_i64().unwrap()).collect();
+        assert_eq!(numbers, [1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        assert!(run("| generate count=1 | outputjson").is_err());
+        assert!(run("| generate count=1 | outputjson path=\"x.json\" format=yaml").is_err());
+    }
+}
@@ -0,0 +1,90 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, scanner::TokenType, wildcard};
+
+pub struct Remove;
+
+// Removes columns from the table, or with -keep removes every column except the listed ones
+// Examples:
+// | remove a, b, c
+// | remove src_*
+// | remove -keep a, b
+impl CommandExecutor for Remove {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut keep = false;
+        let mut patterns: Vec<&String> = Vec::new();
+
+        for arg in args {
+            let mut nodes = arg.expect_type(ParseNodeType::Argument)?.children();
+
+            // -keep is only valid before the first field
+            if let [operator, flag, rest @ ..] = nodes {
+                if operator.variant == ParseNodeType::ArithmeticOperator {
+                    if patterns.is_empty() && !keep && operator.token_type() == TokenType::Sub && field_name(flag)? == "keep" {
+                        keep = true;
+                        nodes = rest;
+                    } else {
+                        return Err("Usage: remove [-keep] <field>[, <field>...]".into());
+                    }
+                }
+            }
+
+            for node in nodes {
+                patterns.push(field_name(node)?);
+            }
+        }
+
+        if patterns.is_empty() {
+            return Err("Usage: remove [-keep] <field>[, <field>...]".into());
+        }
+
+        return Self::remove(&patterns, keep, "remove", state);
+    }
+}
+
+impl Remove {
+    // Removes every column matching one of the patterns, or with keep every column that doesn't match
+    pub fn remove(patterns: &[&String], keep: bool, command: &str, state: &mut Table) -> Result<(), Box<dyn Error>> {
+        for pattern in patterns {
+            if !state.columns().iter().any(|column| wildcard::is_match(pattern, column)) {
+                return Err(format!("Cannot {} {}: no such column", command, pattern).into());
+            }
+        }
+
+        // Remove from the back, so the remaining indices stay valid
+        let columns: Vec<usize> = (0..state.columns().len()).rev().collect();
+
+        for index in columns {
+            let listed = patterns.iter().any(|pattern| wildcard::is_match(pattern, &state.columns()[index]));
+
+            if listed != keep {
+                state.remove_column(index)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::run;
+
+    const TABLE: &str = "| generate count=1 name=\"a\" | eval src_ip = 1 | eval src_port = 2 | eval b = 3";
+
+    #[test]
+    fn removes_or_keeps_matching_columns() {
+        assert_eq!(run(&format!("{} | remove a, src_*", TABLE)).unwrap().columns(), ["b"]);
+        assert_eq!(run(&format!("{} | remove -keep a, src_*", TABLE)).unwrap().columns(), ["a", "src_ip", "src_port"]);
+    }
+
+    #[test]
+    fn rejects_unknown_columns_and_misplaced_flags() {
+        assert!(run(&format!("{} | remove x", TABLE)).is_err());
+        assert!(run(&format!("{} | remove a -keep b", TABLE)).is_err());
+        assert!(run(&format!("{} | remove -drop a", TABLE)).is_err());
+        assert!(run(&format!("{} | remove", TABLE)).is_err());
+    }
+}
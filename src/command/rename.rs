@@ -0,0 +1,94 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, wildcard};
+
+pub struct Rename;
+
+// Renames one or more columns, renames are applied left to right
+// Examples:
+// | rename a as b
+// | rename a as b, c as d
+// | rename src_* as *
+// | rename x as "Total bytes"
+impl CommandExecutor for Rename {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        if args.is_empty() {
+            return Err("Usage: rename <field> as <field>[, <field> as <field>]".into());
+        }
+
+        for arg in args {
+            let arg = arg.expect_type(ParseNodeType::Argument)?;
+
+            // Must be <field> as <field>
+            let (source, keyword, target) = match arg.children() {
+                [source, keyword, target] => (field_name(source)?, field_name(keyword)?, field_name(target)?),
+                _ => return Err("Usage: rename <field> as <field>[, <field> as <field>]".into())
+            };
+
+            if keyword != "as" {
+                return Err(format!("Expected 'as' after {}, got {}", source, keyword).into());
+            }
+
+            Self::rename(source, target, state)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Rename {
+    fn rename(source: &str, target: &str, state: &mut Table) -> Result<(), Box<dyn Error>> {
+        // Work out every (column, new name) pair before touching the table
+        let mut renames: Vec<(usize, String)> = Vec::new();
+
+        for (index, column) in state.columns().iter().enumerate() {
+            if let Some(captures) = wildcard::captures(source, column) {
+                renames.push((index, wildcard::substitute(target, &captures)));
+            }
+        }
+
+        if renames.is_empty() {
+            return Err(format!("Cannot rename {}: no such column", source).into());
+        }
+
+        for (position, (index, name)) in renames.iter().enumerate() {
+            let taken_by_other = state.columns().iter().enumerate()
+                .any(|(other, column)| column == name && renames.iter().all(|(renamed, _)| *renamed != other));
+            let taken_by_rename = renames[..position].iter().any(|(_, other)| other == name);
+
+            if taken_by_other || taken_by_rename {
+                return Err(format!("Cannot rename {} as {}: column already exists", state.columns()[*index], name).into());
+            }
+        }
+
+        for (index, name) in renames {
+            state.rename_column(index, &name)?;
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::run;
+
+    #[test]
+    fn renames_columns_left_to_right() {
+        let table = run("| generate count=1 name=\"src_ip\" | eval src_port = 80 | rename src_* as *, ip as host").unwrap();
+        assert_eq!(table.columns(), ["host", "port"]);
+
+        let table = run("| generate count=1 name=\"a\" | eval b = 1 | rename a as c, c as d").unwrap();
+        assert_eq!(table.columns(), ["d", "b"]);
+    }
+
+    #[test]
+    fn rejects_missing_and_taken_names() {
+        assert!(run("| generate count=1 name=\"a\" | rename x as y").is_err());
+        assert!(run("| generate count=1 name=\"a\" | eval b = 1 | rename a as b").is_err());
+        assert!(run("| generate count=1 name=\"a\" | eval b = 1 | rename * as c").is_err());
+        assert!(run("| generate count=1 name=\"a\" | rename a to b").is_err());
+    }
+}
@@ -0,0 +1,93 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{option, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, pattern, scanner::TokenType, value::Value};
+
+pub struct Rex;
+
+// Extracts new fields from the named capture groups of a regular expression.
+// Rows where the pattern doesn't match keep their values, groups that didn't take part in the match are null.
+// Examples:
+// | rex field=msg "user=(?<user>\w+) id=(?<id>\d+)"
+// | rex field=path "^/(?<section>[^/]+)"
+impl CommandExecutor for Rex {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let usage = "Usage: rex field=<field> \"<pattern>\"";
+
+        let mut field: Option<&String> = None;
+        let mut regex: Option<&String> = None;
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant == ParseNodeType::Value && node.token_type() == TokenType::String {
+                    regex = node.token_value();
+                    continue;
+                }
+
+                let (name, value) = option(node).map_err(|_| usage)?;
+                match name.as_str() {
+                    "field" => field = Some(value),
+                    _ => return Err(format!("Unknown option {} for rex", name).into())
+                }
+            }
+        }
+
+        let (field, regex) = match (field, regex) {
+            (Some(field), Some(regex)) => (field, pattern::compile(regex)?),
+            _ => return Err(usage.into())
+        };
+
+        let names: Vec<&str> = regex.capture_names().flatten().collect();
+        if names.is_empty() {
+            return Err(format!("Pattern for rex has no named groups, e.g. (?<name>\\w+): \"{}\"", regex.as_str()).into());
+        }
+
+        let source = state.get_column(field)
+            .map_err(|_| format!("Cannot rex {}: no such column", field))?;
+
+        let columns = names.iter()
+            .map(|name| state.get_or_create_column(name))
+            .collect::<Result<Vec<usize>, Box<dyn Error>>>()?;
+
+        for index in 0..state.row_count() {
+            let text = match &state.rows()[index][source] {
+                Value::Null => continue,
+                value => value.to_string()
+            };
+
+            let captures = match regex.captures(&text) {
+                Some(captures) => captures,
+                None => continue
+            };
+
+            for (name, &column) in names.iter().zip(&columns) {
+                let value = captures.name(name).map(|group| Value::from(group.as_str())).unwrap_or(Value::Null);
+                state.set_cell(column, index, value)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    const TABLE: &str = r#"| generate count=3 | eval msg = if($rowcount == 2, "nothing here", "user=u" + $rowcount + if($rowcount == 3, " id=9", "")) | eval user = "old""#;
+
+    #[test]
+    fn extracts_named_groups() {
+        let table = run(&format!(r#"{} | rex field=msg "user=(?<user>\w+)( id=(?<id>\d+))?""#, TABLE)).unwrap();
+        assert_eq!(column(&table, "user"), [Value::from("u1"), Value::from("old"), Value::from("u3")]);
+        assert_eq!(column(&table, "id"), [Value::Null, Value::Null, Value::from("9")]);
+    }
+
+    #[test]
+    fn rejects_patterns_without_named_groups() {
+        assert!(run(&format!(r#"{} | rex field=msg "user=(\w+)""#, TABLE)).is_err());
+        assert!(run(&format!(r#"{} | rex field=nope "(?<a>.)""#, TABLE)).is_err());
+        assert!(run(&format!(r#"{} | rex "(?<a>.)""#, TABLE)).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+#![allow(clippy::needless_return)]
+
+use std::{cmp::Ordering, error::Error};
+
+use crate::{command::{field_name, option, parse_count, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, scanner::TokenType, value::Value};
+
+pub struct Sort;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortType {
+    // Numbers numerically, text naturally (file2 before file10), numbers before text
+    Auto,
+    Numeric,
+    Lexicographic
+}
+
+pub struct SortKey {
+    pub column: usize,
+    pub descending: bool,
+    pub sort_type: SortType
+}
+
+// Sorts the rows by one or more fields, rows that compare equal keep their pipeline order.
+// Fields are sorted ascending unless prefixed with '-', empty cells always come last.
+// Examples:
+// | sort -bytes
+// | sort -bytes, +host
+// | sort num(port), str(code) limit=10
+impl CommandExecutor for Sort {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut limit: usize = 0;
+        let mut keys: Vec<SortKey> = Vec::new();
+
+        for arg in args {
+            let mut nodes: Vec<&ParseNode> = Vec::new();
+
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant == ParseNodeType::Assignment {
+                    let (name, value) = option(node)?;
+                    match name.as_str() {
+                        "limit" => limit = parse_count(name, value)?,
+                        _ => return Err(format!("Unknown option {} for sort", name).into())
+                    }
+                } else {
+                    nodes.push(node);
+                }
+            }
+
+            if !nodes.is_empty() {
+                keys.push(Self::key(&nodes, state)?);
+            }
+        }
+
+        if keys.is_empty() {
+            return Err("Usage: sort [limit=<number>] [+|-]<field>[, [+|-]<field>...]".into());
+        }
+
+        let mut rows = state.rows().to_vec();
+        Self::sort(&mut rows, &keys);
+
+        if limit > 0 {
+            rows.truncate(limit);
+        }
+
+        state.set_data(state.columns().to_vec(), rows)?;
+
+        return Ok(());
+    }
+}
+
+impl Sort {
+    // [+|-] <field>
+    // [+|-] num(<field>) | str(<field>) | auto(<field>)
+    // Either as separate nodes, or as a single unary expression (e.g. | dedup host sortby=-time)
+    pub fn key(nodes: &[&ParseNode], state: &Table) -> Result<SortKey, Box<dyn Error>> {
+        let (descending, field) = match nodes {
+            [operator, field] if operator.variant == ParseNodeType::ArithmeticOperator => {
+                (operator.token_type() == TokenType::Sub, *field)
+            },
+            [expression] if expression.variant == ParseNodeType::Expression && expression.children().len() == 2 => {
+                (expression.children()[0].token_type() == TokenType::Sub, &expression.children()[1])
+            },
+            [field] => (false, *field),
+            _ => return Err("Expected a single sort field, separate fields with commas".into())
+        };
+
+        let (sort_type, field) = if field.variant == ParseNodeType::Call {
+            let sort_type = match field.token_value().map(|name| name.as_str()) {
+                Some("auto") => SortType::Auto,
+                Some("num") => SortType::Numeric,
+                Some("str") => SortType::Lexicographic,
+                Some(name) => return Err(format!("Unknown sort type {}, expected auto, num or str", name).into()),
+                None => return Err("Function is missing a name".into())
+            };
+            match field.children() {
+                [field] => (sort_type, field_name(field)?),
+                _ => return Err("Sort types take a single field".into())
+            }
+        } else {
+            (SortType::Auto, field_name(field)?)
+        };
+
+        let column = state.get_column(field)
+            .map_err(|_| format!("Cannot sort by {}: no such column", field))?;
+
+        return Ok(SortKey { column, descending, sort_type });
+    }
+
+    // Stable sort, so ties keep their pipeline order
+    pub fn sort(rows: &mut [Vec<Value>], keys: &[SortKey]) {
+        rows.sort_by(|a, b| {
+            keys.iter()
+                .map(|key| Self::compare_cells(&a[key.column], &b[key.column], key))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    fn compare_cells(a: &Value, b: &Value, key: &SortKey) -> Ordering {
+        let ordering = match (a.is_null(), b.is_null()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) => match key.sort_type {
+                SortType::Auto => Self::compare_auto(a, b),
+                SortType::Numeric => Self::compare_numeric(a, b),
+                SortType::Lexicographic => a.to_string().cmp(&b.to_string())
+            }
+        };
+
+        if key.descending {
+            return ordering.reverse();
+        }
+        return ordering;
+    }
+
+    // Values of the same type compare by value, otherwise numbers come first
+    fn compare_auto(a: &Value, b: &Value) -> Ordering {
+        return match (a, b) {
+            (Value::Bool(_), Value::Bool(_)) |
+            (Value::Timestamp(_), Value::Timestamp(_)) => a.compare(b).unwrap_or(Ordering::Equal),
+            _ => match (a.is_number(), b.is_number()) {
+                (true, true) => a.as_number().unwrap().total_cmp(&b.as_number().unwrap()),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => Self::compare_natural(&a.to_string(), &b.to_string())
+            }
+        };
+    }
+
+    // Values that are not numbers (or strings holding a number) sort after every number
+    fn compare_numeric(a: &Value, b: &Value) -> Ordering {
+        return match (a.as_number(), b.as_number()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.to_string().cmp(&b.to_string())
+        };
+    }
+
+    // Compares runs of digits by their value, so "file2" comes before "file10"
+    fn compare_natural(a: &str, b: &str) -> Ordering {
+        let mut a = a.chars().peekable();
+        let mut b = b.chars().peekable();
+
+        loop {
+            match (a.peek().copied(), b.peek().copied()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                    let x = Self::take_digits(&mut a);
+                    let y = Self::take_digits(&mut b);
+                    let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                    if ordering.is_ne() {
+                        return ordering;
+                    }
+                },
+                (Some(x), Some(y)) => {
+                    if x != y {
+                        return x.cmp(&y);
+                    }
+                    a.next();
+                    b.next();
+                }
+            }
+        }
+    }
+
+    // Digits without leading zeros
+    fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+        let mut digits = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+            if !(digits.is_empty() && c == '0') {
+                digits.push(c);
+            }
+        }
+        return digits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    fn ints(values: &[i64]) -> Vec<Value> {
+        return values.iter().map(|value| Value::Int(*value)).collect();
+    }
+
+    #[test]
+    fn sorts_stably_by_every_field() {
+        // v is 1 2 0 1 2 0
+        let table = run("| generate count=6 | eval id = $rowcount | eval v = $rowcount % 3 | sort -v, +id").unwrap();
+        assert_eq!(column(&table, "id"), ints(&[2, 5, 1, 4, 3, 6]));
+
+        let table = run("| generate count=6 | eval id = $rowcount | eval v = $rowcount % 3 | sort v limit=3").unwrap();
+        assert_eq!(column(&table, "id"), ints(&[3, 6, 1]));
+    }
+
+    #[test]
+    fn sorts_by_type() {
+        let query = "| generate count=4 | eval f = \"file\" + (12 - $rowcount * 2) | eval n = 12 - $rowcount * 2 + \"\"";
+        let files = |values: &[&str]| values.iter().map(|value| Value::from(*value)).collect::<Vec<Value>>();
+
+        assert_eq!(column(&run(&format!("{} | sort f", query)).unwrap(), "f"), files(&["file4", "file6", "file8", "file10"]));
+        assert_eq!(column(&run(&format!("{} | sort str(f)", query)).unwrap(), "f"), files(&["file10", "file4", "file6", "file8"]));
+        assert_eq!(column(&run(&format!("{} | sort -num(n)", query)).unwrap(), "n"), files(&["10", "8", "6", "4"]));
+    }
+
+    #[test]
+    fn puts_empty_cells_last() {
+        let table = run("| generate count=3 | eval v = nullif($rowcount, 2) | sort -v").unwrap();
+        assert_eq!(column(&table, "v"), [Value::Int(3), Value::Int(1), Value::Null]);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_types() {
+        assert!(run("| generate count=1 | sort x").is_err());
+        assert!(run("| generate count=1 name=\"a\" | sort len(a)").is_err());
+        assert!(run("| generate count=1 name=\"a\" | sort").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{option, CommandExecutor}, json, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Spath;
+
+// Extracts fields from JSON text in a column.
+// With a path, the part at the path goes to the output column (Default: the path itself),
+// without one, every leaf goes to a column named after its dotted path, as in inputjson.
+// Rows where the text isn't JSON, or doesn't hold the path, keep their values.
+// Examples:
+// | spath input=payload path="request.headers.host" output=host
+// | spath input=payload path="items[0].id"
+// | spath input=payload
+impl CommandExecutor for Spath {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut input = String::from("_raw");
+        let mut path: Option<&String> = None;
+        let mut output: Option<&String> = None;
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant != ParseNodeType::Assignment {
+                    return Err("Usage: spath [input=<field>] [path=<string> [output=<field>]]".into());
+                }
+
+                let (name, value) = option(node)?;
+                match name.as_str() {
+                    "input" => input = value.clone(),
+                    "path" => path = Some(value),
+                    "output" => output = Some(value),
+                    _ => return Err(format!("Unknown option {} for spath", name).into())
+                }
+            }
+        }
+
+        let source = state.get_column(&input)
+            .map_err(|_| format!("Cannot spath {}: no such column", input))?;
+
+        let steps = match path {
+            Some(path) => Some(json::parse_path(path)?),
+            None if output.is_some() => return Err("Option output needs a path".into()),
+            None => None
+        };
+
+        // The fields found in every row, written once all rows are read
+        let mut extracted: Vec<Vec<(String, Value)>> = Vec::new();
+
+        for row in state.rows() {
+            let mut fields: Vec<(String, Value)> = Vec::new();
+
+            let document = match &row[source] {
+                Value::Null => None,
+                value => serde_json::from_str::<serde_json::Value>(&value.to_string()).ok()
+            };
+
+            if let Some(document) = document {
+                match (&steps, path) {
+                    (Some(steps), Some(path)) => if let Some(value) = json::select(&document, steps) {
+                        fields.push((output.unwrap_or(path).clone(), json::to_value(value)));
+                    },
+                    _ => json::flatten(&document, "", &mut fields)
+                }
+            }
+
+            extracted.push(fields);
+        }
+
+        // The output column exists even when no row holds the path
+        if let Some(output) = output.or(path) {
+            state.get_or_create_column(output)?;
+        }
+
+        for (index, fields) in extracted.into_iter().enumerate() {
+            for (name, value) in fields {
+                let column = state.get_or_create_column(&name)?;
+                state.set_cell(column, index, value)?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    const TABLE: &str = r#"| generate count=3 | eval payload = case($rowcount == 1, "{\"user\": {\"id\": 7}, \"tags\": [\"a\"]}", $rowcount == 2, "not json", true, "{\"user\": {\"id\": 8}}") | eval id = "old""#;
+
+    #[test]
+    fn extracts_the_value_at_a_path() {
+        let table = run(&format!(r#"{} | spath input=payload path="$.user.id" output=id"#, TABLE)).unwrap();
+        assert_eq!(column(&table, "id"), [Value::Int(7), Value::from("old"), Value::Int(8)]);
+
+        let table = run(&format!(r#"{} | spath input=payload path="missing""#, TABLE)).unwrap();
+        assert_eq!(column(&table, "missing"), [Value::Null, Value::Null, Value::Null]);
+    }
+
+    #[test]
+    fn extracts_every_leaf_without_a_path() {
+        let table = run(&format!("{} | spath input=payload", TABLE)).unwrap();
+        assert_eq!(column(&table, "user.id"), [Value::Int(7), Value::Null, Value::Int(8)]);
+        assert_eq!(column(&table, "tags"), [Value::Multivalue(vec!["a".into()]), Value::Null, Value::Null]);
+    }
+
+    #[test]
+    fn rejects_invalid_options() {
+        assert!(run(&format!("{} | spath", TABLE)).is_err());
+        assert!(run(&format!("{} | spath input=payload output=x", TABLE)).is_err());
+        assert!(run(&format!(r#"{} | spath input=payload path="a..b""#, TABLE)).is_err());
+    }
+}
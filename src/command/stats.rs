@@ -0,0 +1,167 @@
+#![allow(clippy::needless_return)]
+
+use std::{collections::HashMap, error::Error};
+
+use crate::{aggregate::{self, Aggregator}, command::{field_name, CommandExecutor}, output::Table, value::Value, parser::{ParseNode, ParseNodeType}};
+
+pub struct Stats;
+
+// The by clause values of a group, and an aggregator for every aggregation
+type Group = (Vec<Value>, Vec<Box<dyn Aggregator>>);
+
+struct Aggregation {
+    function: String,
+    field: Option<usize>,
+    name: String
+}
+
+// Collapses the table into one row per group, with a column for every aggregation.
+// Groups keep the order they first appeared in.
+// Examples:
+// | stats count
+// | stats count, sum(bytes), avg(latency) as avg_lat by host, status
+// | stats dc(user) as users, perc95(latency) by host
+impl CommandExecutor for Stats {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut aggregations: Vec<Aggregation> = Vec::new();
+        let mut by_fields: Vec<&String> = Vec::new();
+        let mut in_by_clause = false;
+
+        let mut nodes = args.iter()
+            .map(|arg| arg.expect_type(ParseNodeType::Argument).map(|arg| arg.children()))
+            .collect::<Result<Vec<&[ParseNode]>, Box<dyn Error>>>()?
+            .into_iter()
+            .flatten();
+
+        while let Some(node) = nodes.next() {
+            if in_by_clause {
+                by_fields.push(field_name(node)?);
+                continue;
+            }
+
+            if node.variant == ParseNodeType::Call {
+                let function = node.token_value().ok_or("Function is missing a name")?;
+                let field = match node.children() {
+                    [] => None,
+                    [field] => Some(Self::resolve(field_name(field)?, state)?),
+                    _ => return Err(format!("Aggregation {} takes a single field", function).into())
+                };
+                let name = match node.child(0) {
+                    Some(field) => format!("{}({})", function, field_name(field)?),
+                    None => function.clone()
+                };
+
+                aggregations.push(Aggregation { function: function.clone(), field, name });
+                continue;
+            }
+
+            let name = field_name(node)?;
+
+            if name == "by" {
+                in_by_clause = true;
+            } else if name == "as" {
+                let alias = nodes.next().ok_or("Expected a name after 'as'")?;
+                let aggregation = aggregations.last_mut().ok_or("Expected an aggregation before 'as'")?;
+                aggregation.name = field_name(alias)?.clone();
+            } else {
+                aggregations.push(Aggregation { function: name.clone(), field: None, name: name.clone() });
+            }
+        }
+
+        if aggregations.is_empty() || (in_by_clause && by_fields.is_empty()) {
+            return Err("Usage: stats <aggregation>[(<field>)] [as <name>][, ...] [by <field>[, <field>...]]".into());
+        }
+
+        for aggregation in &aggregations {
+            let aggregator = aggregate::create(&aggregation.function)
+                .ok_or(format!("Unknown aggregation {}", aggregation.function))?;
+
+            if aggregation.field.is_none() && aggregator.requires_field() {
+                return Err(format!("Aggregation {} requires a field, e.g. {}(bytes)", aggregation.function, aggregation.function).into());
+            }
+        }
+
+        let by_columns = by_fields.iter()
+            .map(|field| Self::resolve(field, state))
+            .collect::<Result<Vec<usize>, Box<dyn Error>>>()?;
+
+        let mut columns: Vec<String> = by_fields.iter().map(|&field| field.clone()).collect();
+        for aggregation in &aggregations {
+            if columns.contains(&aggregation.name) {
+                return Err(format!("Cannot stats {}: column already exists", aggregation.name).into());
+            }
+            columns.push(aggregation.name.clone());
+        }
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut positions: HashMap<Vec<Value>, usize> = HashMap::new();
+
+        // Without a by clause there is always exactly one group, even for an empty table
+        if by_columns.is_empty() {
+            positions.insert(Vec::new(), 0);
+            groups.push((Vec::new(), Self::aggregators(&aggregations)));
+        }
+
+        for row in state.rows() {
+            let key: Vec<Value> = by_columns.iter().map(|&i| row[i].clone()).collect();
+
+            let position = *positions.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, Self::aggregators(&aggregations)));
+                groups.len() - 1
+            });
+
+            for (aggregation, aggregator) in aggregations.iter().zip(groups[position].1.iter_mut()) {
+                match aggregation.field.map(|column| &row[column]) {
+                    // Every value of a multivalue counts on its own
+                    Some(Value::Multivalue(values)) => values.iter().filter(|value| !value.is_null()).for_each(|value| aggregator.add(value)),
+                    Some(Value::Null) => continue,
+                    Some(value) => aggregator.add(value),
+                    None => aggregator.add(&Value::Null)
+                }
+            }
+        }
+
+        let rows: Vec<Vec<Value>> = groups.into_iter()
+            .map(|(mut key, aggregators)| {
+                key.extend(aggregators.iter().map(|aggregator| aggregator.result()));
+                key
+            })
+            .collect();
+
+        state.set_data(columns, rows)?;
+
+        return Ok(());
+    }
+}
+
+impl Stats {
+    fn resolve(field: &str, state: &Table) -> Result<usize, Box<dyn Error>> {
+        return state.get_column(field).map_err(|_| format!("Cannot stats {}: no such column", field).into());
+    }
+
+    fn aggregators(aggregations: &[Aggregation]) -> Vec<Box<dyn Aggregator>> {
+        // Every function was validated before the table is read
+        return aggregations.iter()
+            .map(|aggregation| aggregate::create(&aggregation.function).unwrap())
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, value::Value};
+
+    #[test]
+    fn aggregates_every_value_of_a_multivalue() {
+        let table = run("| generate count=2 | eval tags = split(if($rowcount == 1, \"a,b\", \"b,c\"), \",\") | stats values(tags), dc(tags), count(tags), count").unwrap();
+        assert_eq!(table.rows(), [vec![
+            Value::Multivalue(vec!["a".into(), "b".into(), "c".into()]), Value::Int(3), Value::Int(4), Value::Int(2)
+        ]]);
+    }
+
+    #[test]
+    fn sums_nothing_to_null() {
+        let table = run("| generate count=2 | eval host = \"web\" | eval n = $rowcount | stats sum(host), sum(n)").unwrap();
+        assert_eq!(table.rows(), [vec![Value::Null, Value::Int(3)]]);
+    }
+}
@@ -0,0 +1,75 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{field_name, CommandExecutor}, output, parser::{ParseNode, ParseNodeType}, wildcard};
+
+pub struct Table;
+
+// Keeps only the listed columns, in the listed order
+// Examples:
+// | table a, b, c
+// | table host, src_*
+impl CommandExecutor for Table {
+    fn execute(args: &[ParseNode], state: &mut output::Table) -> Result<(), Box<dyn Error>> {
+        let mut patterns: Vec<&String> = Vec::new();
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                patterns.push(field_name(node)?);
+            }
+        }
+
+        if patterns.is_empty() {
+            return Err("Usage: table <field>[, <field>...]".into());
+        }
+
+        return Self::project(&patterns, "table", state);
+    }
+}
+
+impl Table {
+    // Columns matched by a wildcard keep their current order, a column is only ever listed once
+    pub fn project(patterns: &[&String], command: &str, state: &mut output::Table) -> Result<(), Box<dyn Error>> {
+        let mut indices: Vec<usize> = Vec::new();
+
+        for pattern in patterns {
+            let matches: Vec<usize> = state.columns().iter().enumerate()
+                .filter(|(_, column)| wildcard::is_match(pattern, column))
+                .map(|(index, _)| index)
+                .collect();
+
+            if matches.is_empty() {
+                return Err(format!("Cannot {} {}: no such column", command, pattern).into());
+            }
+
+            for index in matches {
+                if !indices.contains(&index) {
+                    indices.push(index);
+                }
+            }
+        }
+
+        return state.select_columns(&indices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    const TABLE: &str = "| generate count=1 name=\"a\" | eval src_ip = 1 | eval b = 2 | eval src_port = 3";
+
+    #[test]
+    fn keeps_the_listed_columns_in_order() {
+        let table = run(&format!("{} | table b, src_*, b", TABLE)).unwrap();
+        assert_eq!(table.columns(), ["b", "src_ip", "src_port"]);
+        assert_eq!(column(&table, "src_port"), [Value::Int(3)]);
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        assert!(run(&format!("{} | table b, x", TABLE)).is_err());
+        assert!(run(&format!("{} | table", TABLE)).is_err());
+    }
+}
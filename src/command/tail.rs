@@ -0,0 +1,52 @@
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{command::{head::{Head, Limit}, CommandExecutor}, output::Table, parser::ParseNode};
+
+pub struct Tail;
+
+// Keeps the last rows of the table, the predicate forms are checked from the last row upwards
+// Examples:
+// | tail
+// | tail 5
+// | tail while=(status == 200)
+// | tail until=(bytes > 1000)
+impl CommandExecutor for Tail {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let row_count = state.rows().len();
+
+        let start = match Head::limit(args, "tail")? {
+            Limit::Count(count) => row_count.saturating_sub(count),
+            Limit::While(condition) => Head::first_row(state, (0..row_count).rev(), condition, false)?.map_or(0, |index| index + 1),
+            Limit::Until(condition) => Head::first_row(state, (0..row_count).rev(), condition, true)?.map_or(0, |index| index + 1)
+        };
+
+        state.keep_rows(start, row_count);
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    fn ids(query: &str) -> Vec<Value> {
+        return column(&run(&format!("| generate count=12 | eval id = $rowcount {}", query)).unwrap(), "id");
+    }
+
+    #[test]
+    fn keeps_the_last_rows() {
+        assert_eq!(ids("| tail").len(), 10);
+        assert_eq!(ids("| tail 2"), [Value::Int(11), Value::Int(12)]);
+        assert_eq!(ids("| tail 20").len(), 12);
+    }
+
+    #[test]
+    fn checks_the_predicate_from_the_last_row_upwards() {
+        assert_eq!(ids("| tail while=(id > 10)"), [Value::Int(11), Value::Int(12)]);
+        assert_eq!(ids("| tail until=(id % 5 == 0)"), [Value::Int(11), Value::Int(12)]);
+        assert_eq!(ids("| tail until=(id > 0)"), []);
+    }
+}
@@ -0,0 +1,174 @@
+#![allow(clippy::needless_return)]
+
+use std::{collections::HashMap, error::Error};
+
+use crate::{command::{field_name, option, parse_bool, parse_count, CommandExecutor}, output::Table, parser::{ParseNode, ParseNodeType}, value::Value};
+
+pub struct Top;
+
+// Replaces the table with the most common values of the given fields, along with their count and percentage.
+// With a by clause, the values are counted (and limited) separately for every group.
+// Examples:
+// | top host
+// | top limit=3 host, status
+// | top status showperc=false by host
+impl CommandExecutor for Top {
+    fn execute(args: &[ParseNode], state: &mut Table) -> Result<(), Box<dyn Error>> {
+        let mut limit: usize = 10;
+        let mut show_count = true;
+        let mut show_percent = true;
+
+        let mut fields: Vec<&String> = Vec::new();
+        let mut by_fields: Vec<&String> = Vec::new();
+        let mut in_by_clause = false;
+
+        for arg in args {
+            for node in arg.expect_type(ParseNodeType::Argument)?.children() {
+                if node.variant == ParseNodeType::Assignment {
+                    let (name, value) = option(node)?;
+                    match name.as_str() {
+                        "limit" => limit = parse_count(name, value)?,
+                        "showcount" => show_count = parse_bool(name, value)?,
+                        "showperc" => show_percent = parse_bool(name, value)?,
+                        _ => return Err(format!("Unknown option {} for top", name).into())
+                    }
+                    continue;
+                }
+
+                let name = field_name(node)?;
+
+                if name == "by" && !in_by_clause {
+                    in_by_clause = true;
+                } else if in_by_clause {
+                    by_fields.push(name);
+                } else {
+                    fields.push(name);
+                }
+            }
+        }
+
+        if fields.is_empty() || (in_by_clause && by_fields.is_empty()) {
+            return Err("Usage: top [limit=<number>] [showcount=<bool>] [showperc=<bool>] <field>[, <field>...] [by <field>[, <field>...]]".into());
+        }
+
+        let field_columns = Self::resolve(&fields, state)?;
+        let by_columns = Self::resolve(&by_fields, state)?;
+
+        // Count every distinct (group, values) pair, remembering the order they first appeared in
+        let mut counts: Vec<(Vec<Value>, Vec<Value>, usize)> = Vec::new();
+        let mut positions: HashMap<(Vec<Value>, Vec<Value>), usize> = HashMap::new();
+        let mut group_totals: HashMap<Vec<Value>, usize> = HashMap::new();
+        let mut group_order: HashMap<Vec<Value>, usize> = HashMap::new();
+
+        for row in state.rows() {
+            let group: Vec<Value> = by_columns.iter().map(|&i| row[i].clone()).collect();
+            let values: Vec<Value> = field_columns.iter().map(|&i| row[i].clone()).collect();
+
+            let order = group_order.len();
+            group_order.entry(group.clone()).or_insert(order);
+            *group_totals.entry(group.clone()).or_insert(0) += 1;
+
+            let key = (group, values);
+            if let Some(&position) = positions.get(&key) {
+                counts[position].2 += 1;
+            } else {
+                positions.insert(key.clone(), counts.len());
+                counts.push((key.0, key.1, 1));
+            }
+        }
+
+        // Groups keep their pipeline order, values are sorted by count (ties keep their pipeline order)
+        counts.sort_by(|a, b| group_order[&a.0].cmp(&group_order[&b.0]).then(b.2.cmp(&a.2)));
+
+        let mut columns: Vec<String> = by_fields.iter().chain(fields.iter()).map(|&f| f.clone()).collect();
+        for (name, shown) in [("count", show_count), ("percent", show_percent)] {
+            if !shown {
+                continue;
+            }
+            if columns.iter().any(|column| column == name) {
+                return Err(format!("Cannot top {}: column already exists", name).into());
+            }
+            columns.push(String::from(name));
+        }
+
+        let mut rows: Vec<Vec<Value>> = Vec::new();
+        let mut taken: HashMap<&Vec<Value>, usize> = HashMap::new();
+
+        for (group, values, count) in &counts {
+            let taken = taken.entry(group).or_insert(0);
+            if limit > 0 && *taken >= limit {
+                continue;
+            }
+            *taken += 1;
+
+            let mut row: Vec<Value> = group.iter().chain(values.iter()).cloned().collect();
+            if show_count {
+                row.push(Value::Int(*count as i64));
+            }
+            if show_percent {
+                let percent = *count as f64 * 100.0 / group_totals[group] as f64;
+                row.push(Value::Float((percent * 100.0).round() / 100.0));
+            }
+            rows.push(row);
+        }
+
+        state.set_data(columns, rows)?;
+
+        return Ok(());
+    }
+}
+
+impl Top {
+    fn resolve(fields: &[&String], state: &Table) -> Result<Vec<usize>, Box<dyn Error>> {
+        return fields.iter()
+            .map(|field| state.get_column(field).map_err(|_| format!("Cannot top {}: no such column", field).into()))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::run, value::Value};
+
+    // Five rows, n is 1 0 0 1 0 and parity 1 0 1 0 1
+    const TABLE: &str = "| generate count=5 | eval n = $rowcount % 3 % 2 | eval parity = $rowcount % 2";
+
+    #[test]
+    fn counts_values_by_how_often_they_appear() {
+        let table = run(&format!("{} | top n", TABLE)).unwrap();
+        assert_eq!(table.columns(), ["n", "count", "percent"]);
+        assert_eq!(table.rows(), [
+            vec![Value::Int(0), Value::Int(3), Value::Float(60.0)],
+            vec![Value::Int(1), Value::Int(2), Value::Float(40.0)]
+        ]);
+    }
+
+    #[test]
+    fn limits_every_group_separately() {
+        let table = run(&format!("{} | top limit=1 showperc=false n by parity", TABLE)).unwrap();
+        assert_eq!(table.columns(), ["parity", "n", "count"]);
+        assert_eq!(table.rows(), [
+            vec![Value::Int(1), Value::Int(0), Value::Int(2)],
+            // A tie keeps the value seen first
+            vec![Value::Int(0), Value::Int(0), Value::Int(1)]
+        ]);
+    }
+
+    #[test]
+    fn rejects_unknown_fields_and_options() {
+        assert!(run(&format!("{} | top x", TABLE)).is_err());
+        assert!(run(&format!("{} | top n by", TABLE)).is_err());
+        assert!(run(&format!("{} | top limit=-1 n", TABLE)).is_err());
+        assert!(run(&format!("{} | top size=1 n", TABLE)).is_err());
+    }
+
+    #[test]
+    fn rejects_fields_named_like_the_added_columns() {
+        let err = run(&format!("{} | eval count = n | top count", TABLE)).err().unwrap().to_string();
+        assert_eq!(err, "Cannot top count: column already exists");
+        assert!(run(&format!("{} | eval percent = n | top n by percent", TABLE)).is_err());
+
+        let table = run(&format!("{} | eval count = n | top showcount=false count", TABLE)).unwrap();
+        assert_eq!(table.columns(), ["count", "percent"]);
+    }
+}
@@ -1,64 +1,73 @@
-use crate::{command::CommandExecutor, parser::ParseNodeType, scanner::TokenType};
+#![allow(clippy::needless_return)]
+
+use crate::{command::CommandExecutor, parser::ParseNodeType};
 
 pub struct Where;
 
 impl CommandExecutor for Where {
     fn execute(args: &[crate::parser::ParseNode], state: &mut crate::output::Table) -> Result<(), Box<dyn std::error::Error>> {
         
-        // Must be comparison
-        let argument = args[0]
-            .expect_type(ParseNodeType::Argument)?
-            .child(0).unwrap()
-            .expect_type(ParseNodeType::Comparison)?;
-
-        // Must be value
-        let lval = argument
-            .child(0).unwrap()
-            .expect_type(ParseNodeType::Value)?;
-
-        // Must be operator
-        let operator = argument
-            .child(1).unwrap()
-            .expect_type(ParseNodeType::ComparisonOperator)?
-            .token_type();
-
-        // Must be value
-        let rval = argument
-            .child(2).unwrap()
-            .expect_type(ParseNodeType::Value)?;
+        // Must be a single comparison or condition
+        let argument = match args {
+            [arg] => match arg.expect_type(ParseNodeType::Argument)?.children() {
+                [node] if matches!(node.variant, ParseNodeType::Comparison | ParseNodeType::Condition | ParseNodeType::Call | ParseNodeType::Value) => node,
+                _ => return Err("Usage: where <condition>".into())
+            },
+            _ => return Err("Usage: where <condition>".into())
+        };
 
         let mut counter = 0;
 
-        loop {
-            counter += 1;
-            
-            if counter >= state.row_count() {
-                break;
-            }
-
+        while counter < state.row_count() {
             let env = state.get_row_env(counter)?;
 
-            let lval_evaluated = <Where as CommandExecutor>::evaluate(lval, &env)?;
-            let lval_evaluated = lval_evaluated.token_value().unwrap();
-            let rval_evaluated = <Where as CommandExecutor>::evaluate(rval, &env)?;
-            let rval_evaluated = rval_evaluated.token_value().unwrap();
+            let is_criteria_met = <Where as CommandExecutor>::test(argument, &env)?;
             
-            let is_criteria_met = match operator {
-                TokenType::Eeq => lval_evaluated == rval_evaluated,
-                TokenType::Neq => lval_evaluated != rval_evaluated,
-                TokenType::Gt  => lval_evaluated.parse::<usize>()? > rval_evaluated.parse::<usize>()?,
-                TokenType::Lt  => lval_evaluated.parse::<usize>()? < rval_evaluated.parse::<usize>()?,
-                TokenType::Gte => lval_evaluated.parse::<usize>()? >= rval_evaluated.parse::<usize>()?,
-                TokenType::Lte => lval_evaluated.parse::<usize>()? <= rval_evaluated.parse::<usize>()?,
-                _ => false
-            };
-            
-            if !is_criteria_met {
+            if is_criteria_met {
+                counter += 1;
+            } else {
                 state.remove_row(counter)?;
-                counter -= 1;
             }
         };
 
         return Ok(());
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::{interpreter::{column, run}, value::Value};
+
+    fn ids(condition: &str) -> Vec<Value> {
+        let query = format!("| generate count=6 | eval id = $rowcount | eval even = $rowcount % 2 == 0 | eval v = nullif($rowcount, 3) | where {}", condition);
+        return column(&run(&query).unwrap(), "id");
+    }
+
+    fn ints(values: &[i64]) -> Vec<Value> {
+        return values.iter().map(|value| Value::Int(*value)).collect();
+    }
+
+    #[test]
+    fn combines_conditions() {
+        assert_eq!(ids("id > 2 and id < 5"), ints(&[3, 4]));
+        assert_eq!(ids("id < 2 or id > 5"), ints(&[1, 6]));
+        assert_eq!(ids("id == 1 or id == 2 and id == 3"), ints(&[1]));
+        assert_eq!(ids("(id == 1 or id == 2) and not id == 1"), ints(&[2]));
+        assert_eq!(ids("!(id > 1) or !even and id > 4"), ints(&[1, 5]));
+        assert_eq!(ids("even"), ints(&[2, 4, 6]));
+        assert_eq!(ids("not not even and true"), ints(&[2, 4, 6]));
+    }
+
+    #[test]
+    fn treats_comparisons_with_null_as_false() {
+        assert_eq!(ids("v >= 3"), ints(&[4, 5, 6]));
+        assert_eq!(ids("not v >= 3"), ints(&[1, 2, 3]));
+        assert_eq!(ids("v == null"), []);
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_conditions() {
+        assert!(run("| generate count=1 | where 1 + 1").is_err());
+        assert!(run("| generate count=1 | where \"yes\"").is_err());
+        assert!(run("| generate count=1 | where").is_err());
+    }
+}
@@ -0,0 +1,199 @@
+// CSV records as described in RFC 4180. Fields holding the delimiter, the quote or a line break are quoted,
+// and a quote inside a quoted field is written twice.
+
+#![allow(clippy::needless_return)]
+
+use std::{error::Error, iter::Peekable, str::Chars};
+
+pub struct Format {
+    pub delimiter: char,
+    // None reads quotes as ordinary characters
+    pub quote: Option<char>
+}
+
+pub struct Record {
+    // The line of the file the record starts on
+    pub line: usize,
+    pub fields: Vec<String>
+}
+
+pub struct Reader<'a> {
+    chars: Peekable<Chars<'a>>,
+    format: &'a Format,
+    line: usize
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(text: &'a str, format: &'a Format) -> Self {
+        Self {
+            chars: text.strip_prefix('\u{feff}').unwrap_or(text).chars().peekable(),
+            format,
+            line: 1
+        }
+    }
+
+    // The next record, blank lines are skipped
+    pub fn next_record(&mut self) -> Result<Option<Record>, Box<dyn Error>> {
+        while self.newline() {
+            self.line += 1;
+        }
+
+        if self.chars.peek().is_none() {
+            return Ok(None);
+        }
+
+        let start = self.line;
+        let mut fields: Vec<String> = Vec::new();
+
+        loop {
+            let field = match self.chars.peek() {
+                Some(c) if Some(*c) == self.format.quote => self.quoted(start)?,
+                _ => self.unquoted()?
+            };
+            fields.push(field);
+
+            if self.newline() {
+                self.line += 1;
+                return Ok(Some(Record { line: start, fields }));
+            }
+
+            match self.chars.next() {
+                None => return Ok(Some(Record { line: start, fields })),
+                Some(c) if c == self.format.delimiter => continue,
+                Some(c) => return Err(format!("line {}: unexpected character {:?} after a quoted field", self.line, c).into())
+            }
+        }
+    }
+
+    // Consumes a line break (\n or \r\n) if one is next
+    fn newline(&mut self) -> bool {
+        if self.chars.next_if_eq(&'\n').is_some() {
+            return true;
+        }
+
+        let mut lookahead = self.chars.clone();
+        if lookahead.next() == Some('\r') && lookahead.next() == Some('\n') {
+            self.chars = lookahead;
+            return true;
+        }
+
+        return false;
+    }
+
+    fn unquoted(&mut self) -> Result<String, Box<dyn Error>> {
+        let mut field = String::new();
+
+        while let Some(c) = self.chars.peek().copied() {
+            if c == self.format.delimiter || c == '\n' || (c == '\r' && self.chars.clone().nth(1) == Some('\n')) {
+                break;
+            }
+            if Some(c) == self.format.quote {
+                return Err(format!("line {}: quote inside a field that isn't quoted", self.line).into());
+            }
+
+            field.push(c);
+            self.chars.next();
+        }
+
+        return Ok(field);
+    }
+
+    fn quoted(&mut self, start: usize) -> Result<String, Box<dyn Error>> {
+        let quote = self.chars.next();
+        let mut field = String::new();
+
+        loop {
+            match self.chars.next() {
+                None => return Err(format!("line {}: quoted field is never closed", start).into()),
+                // A doubled quote is a quote, a single one ends the field
+                c if c == quote => {
+                    if self.chars.next_if(|next| Some(*next) == quote).is_none() {
+                        return Ok(field);
+                    }
+                    field.push(quote.unwrap());
+                },
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+        }
+    }
+}
+
+// A record as a line of text, ending with a line break
+pub fn write_record(fields: &[String], format: &Format) -> String {
+    let mut line = String::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            line.push(format.delimiter);
+        }
+
+        let needs_quotes = field.chars().any(|c| c == format.delimiter || c == '\n' || c == '\r' || Some(c) == format.quote);
+        match format.quote {
+            Some(quote) if needs_quotes => {
+                line.push(quote);
+                line.push_str(&field.replace(quote, &format!("{}{}", quote, quote)));
+                line.push(quote);
+            },
+            _ => line.push_str(field)
+        }
+    }
+
+    line.push('\n');
+    return line;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: Format = Format { delimiter: ',', quote: Some('"') };
+
+    // The line and fields of every record
+    type Records = Vec<(usize, Vec<String>)>;
+
+    fn records(text: &str, format: &Format) -> Result<Records, Box<dyn Error>> {
+        let mut reader = Reader::new(text, format);
+        let mut records = Vec::new();
+        while let Some(Record { line, fields }) = reader.next_record()? {
+            records.push((line, fields));
+        }
+        return Ok(records);
+    }
+
+    fn fields(values: &[&str]) -> Vec<String> {
+        return values.iter().map(|value| value.to_string()).collect();
+    }
+
+    #[test]
+    fn reads_quoted_fields_and_line_breaks() {
+        let text = "\u{feff}a,b\r\n\n\"x, \"\"y\"\"\",\"two\nlines\"\n,\n";
+        assert_eq!(records(text, &CSV).unwrap(), [
+            (1, fields(&["a", "b"])),
+            (3, fields(&["x, \"y\"", "two\nlines"])),
+            (5, fields(&["", ""]))
+        ]);
+
+        let tsv = Format { delimiter: '\t', quote: None };
+        assert_eq!(records("a\"b\tc", &tsv).unwrap(), [(1, fields(&["a\"b", "c"]))]);
+    }
+
+    #[test]
+    fn reports_the_line_of_malformed_records() {
+        assert_eq!(records("a\n\"open,b\n", &CSV).unwrap_err().to_string(), "line 2: quoted field is never closed");
+        assert_eq!(records("a\n\"x\"y\n", &CSV).unwrap_err().to_string(), "line 2: unexpected character 'y' after a quoted field");
+        assert_eq!(records("a\nb\"c\n", &CSV).unwrap_err().to_string(), "line 2: quote inside a field that isn't quoted");
+    }
+
+    #[test]
+    fn writes_records_that_read_back() {
+        let values = fields(&["plain", "a,b", "say \"hi\"", "two\nlines", ""]);
+        let line = write_record(&values, &CSV);
+        assert_eq!(line, "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\n");
+        assert_eq!(records(&line, &CSV).unwrap(), [(1, values)]);
+    }
+}
@@ -0,0 +1,153 @@
+// Conditional functions. if, case and coalesce only evaluate the arguments they need,
+// so e.g. if(n == 0, 0, total / n) never divides by zero.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{function::{truthy, Arguments, Function, Implementation::{Eager, Lazy}}, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "if", min_args: 3, max_args: Some(3), propagates_null: false, implementation: Lazy(if_) },
+    Function { name: "case", min_args: 2, max_args: None, propagates_null: false, implementation: Lazy(case) },
+    Function { name: "coalesce", min_args: 1, max_args: None, propagates_null: false, implementation: Lazy(coalesce) },
+    Function { name: "nullif", min_args: 2, max_args: Some(2), propagates_null: false, implementation: Eager(nullif) },
+    Function { name: "like", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(like) },
+];
+
+// if(condition, then, else)
+fn if_(args: &mut dyn Arguments) -> Result<Value, Box<dyn Error>> {
+    if truthy(args.evaluate(0)?, "condition")? {
+        return args.evaluate(1);
+    }
+    return args.evaluate(2);
+}
+
+// case(condition, value, ...), the value of the first condition that holds, or null when none does.
+// Use true as the last condition for a default.
+fn case(args: &mut dyn Arguments) -> Result<Value, Box<dyn Error>> {
+    let count = args.count();
+    if !count.is_multiple_of(2) {
+        return Err(format!("expects pairs of conditions and values, got {} arguments", count).into());
+    }
+
+    for index in (0..count).step_by(2) {
+        if truthy(args.evaluate(index)?, &format!("condition {}", index / 2 + 1))? {
+            return args.evaluate(index + 1);
+        }
+    }
+
+    return Ok(Value::Null);
+}
+
+// The first value that isn't null
+fn coalesce(args: &mut dyn Arguments) -> Result<Value, Box<dyn Error>> {
+    for index in 0..args.count() {
+        let value = args.evaluate(index)?;
+        if !value.is_null() {
+            return Ok(value);
+        }
+    }
+
+    return Ok(Value::Null);
+}
+
+// nullif(x, y), null when x equals y, otherwise x
+fn nullif(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    if args[0].compare(&args[1]).is_some_and(|ordering| ordering.is_eq()) {
+        return Ok(Value::Null);
+    }
+    return Ok(args[0].clone());
+}
+
+// like(x, pattern), where '%' matches any run of characters, '_' a single character and '\' escapes the next one
+fn like(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let text: Vec<char> = args[0].to_string().chars().collect();
+
+    // None stands for '%', Some(None) for '_'
+    let mut pattern: Vec<Option<Option<char>>> = Vec::new();
+    let mut chars = args[1].to_string().chars().collect::<Vec<char>>().into_iter();
+
+    while let Some(c) = chars.next() {
+        pattern.push(match c {
+            '%' => None,
+            '_' => Some(None),
+            '\\' => Some(Some(chars.next().unwrap_or('\\'))),
+            c => Some(Some(c))
+        });
+    }
+
+    return Ok(Value::Bool(matches(&pattern, &text)));
+}
+
+// Backtracks to the last '%' only, which keeps matching linear in practice
+fn matches(pattern: &[Option<Option<char>>], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(None) => {
+                backtrack = Some((p, t));
+                p += 1;
+            },
+            Some(Some(expected)) if expected.is_none_or(|c| c == text[t]) => {
+                p += 1;
+                t += 1;
+            },
+            _ => match backtrack {
+                // Let the last '%' take one more character
+                Some((star, start)) => {
+                    backtrack = Some((star, start + 1));
+                    p = star + 1;
+                    t = start + 1;
+                },
+                None => return false
+            }
+        }
+    }
+
+    return pattern[p..].iter().all(Option::is_none);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter;
+
+    fn evaluate(expression: &str) -> Value {
+        return interpreter::evaluate(expression).unwrap();
+    }
+
+    #[test]
+    fn evaluates_only_the_arguments_needed() {
+        assert_eq!(evaluate("if(1 > 2, 1 / \"x\", \"no\")"), Value::from("no"));
+        assert_eq!(evaluate("if(null, 1, 2)"), Value::Int(2));
+        assert_eq!(evaluate("case(1 > 2, \"a\", 2 > 1, \"b\", 1 / \"x\", \"c\")"), Value::from("b"));
+        assert_eq!(evaluate("case(false, 1)"), Value::Null);
+        assert_eq!(evaluate("coalesce(null, nullif(1, 1), 3, 1 / \"x\")"), Value::Int(3));
+        assert!(interpreter::evaluate("if(1, 2, 3)").is_err());
+        assert!(interpreter::evaluate("case(true, 1, false)").is_err());
+    }
+
+    #[test]
+    fn compares_with_nullif_and_in() {
+        assert_eq!(evaluate("nullif(\"a\", \"a\")"), Value::Null);
+        assert_eq!(evaluate("nullif(1, 2)"), Value::Int(1));
+        assert_eq!(evaluate("2 in (1, 1 + 1, 3)"), Value::Bool(true));
+        assert_eq!(evaluate("\"2\" in (1, 3)"), Value::Bool(false));
+    }
+
+    #[test]
+    fn matches_like_patterns() {
+        let like = |text: &str, pattern: &str| like(&[text.into(), pattern.into()]).unwrap();
+
+        assert_eq!(like("web-01", "web%"), Value::Bool(true));
+        assert_eq!(like("web-01", "web-_"), Value::Bool(false));
+        assert_eq!(like("web-01", "%-__"), Value::Bool(true));
+        assert_eq!(like("50%", "50\\%"), Value::Bool(true));
+        assert_eq!(like("500", "50\\%"), Value::Bool(false));
+        assert_eq!(like("é", "_"), Value::Bool(true));
+        assert_eq!(like("", "%"), Value::Bool(true));
+    }
+}
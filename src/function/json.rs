@@ -0,0 +1,45 @@
+// JSON functions, working on the text of the value
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{function::{Function, Implementation::Eager}, json, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "json_extract", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(json_extract) },
+];
+
+// json_extract(x, path), the part of the JSON text at the path, e.g. json_extract(payload, "$.items[0].id").
+// Null when x isn't JSON or the path isn't in it, arrays are multivalues and objects are JSON text.
+fn json_extract(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let path = args[1].to_string();
+    let steps = json::parse_path(&path)
+        .map_err(|_| format!("expects a JSON path as path, got \"{}\"", path))?;
+
+    let document = match serde_json::from_str::<serde_json::Value>(&args[0].to_string()) {
+        Ok(document) => document,
+        Err(_) => return Ok(Value::Null)
+    };
+
+    return Ok(json::select(&document, &steps).map(json::to_value).unwrap_or(Value::Null));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_the_part_at_the_path() {
+        let text = Value::from(r#"{"user": {"id": 7}, "items": [3, 4], "meta": {"ok": true}}"#);
+        let extract = |path: &str| json_extract(&[text.clone(), path.into()]).unwrap();
+
+        assert_eq!(extract("$.user.id"), Value::Int(7));
+        assert_eq!(extract("items"), Value::Multivalue(vec![Value::Int(3), Value::Int(4)]));
+        assert_eq!(extract("items[-1]"), Value::Int(4));
+        assert_eq!(extract("meta"), Value::from(r#"{"ok":true}"#));
+        assert_eq!(extract("user.name"), Value::Null);
+        assert_eq!(json_extract(&["not json".into(), "a".into()]).unwrap(), Value::Null);
+        assert!(json_extract(&[text, "items[".into()]).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+// Math and number formatting functions.
+// Results that aren't a real number (e.g. sqrt(-1), log(0)) are null, like a division by zero.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::Cell, error::Error, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{function::{flatten, integer, number, Function, Implementation::Eager}, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "abs", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(abs) },
+    Function { name: "round", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(round) },
+    Function { name: "floor", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(floor) },
+    Function { name: "ceil", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(ceil) },
+    Function { name: "sqrt", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(sqrt) },
+    Function { name: "pow", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(pow) },
+    Function { name: "log", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(log) },
+    Function { name: "ln", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(ln) },
+    Function { name: "exp", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(exp) },
+    Function { name: "min", min_args: 1, max_args: None, propagates_null: false, implementation: Eager(min) },
+    Function { name: "max", min_args: 1, max_args: None, propagates_null: false, implementation: Eager(max) },
+    Function { name: "clamp", min_args: 3, max_args: Some(3), propagates_null: true, implementation: Eager(clamp) },
+    Function { name: "random", min_args: 0, max_args: Some(0), propagates_null: false, implementation: Eager(random) },
+    Function { name: "tonumber", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(tonumber) },
+    Function { name: "tostring", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(tostring) },
+];
+
+fn finite(value: f64) -> Value {
+    if value.is_finite() {
+        return Value::Float(value);
+    }
+    return Value::Null;
+}
+
+// Whole floats become ints when they fit
+fn whole(value: f64) -> Value {
+    if value.is_finite() && value.abs() < i64::MAX as f64 {
+        return Value::Int(value as i64);
+    }
+    return finite(value);
+}
+
+fn float(args: &[Value], index: usize, name: &str) -> Result<f64, Box<dyn Error>> {
+    return Ok(number(args, index, name)?.as_number().unwrap());
+}
+
+fn abs(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match number(args, 0, "x")? {
+        Value::Int(value) => value.checked_abs().map(Value::Int).unwrap_or(Value::Float((value as f64).abs())),
+        value => Value::Float(value.as_number().unwrap().abs())
+    });
+}
+
+// round(x[, digits]), halves round away from zero and negative digits round to tens, hundreds, ...
+fn round(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let digits = match args.get(1) {
+        Some(_) => integer(args, 1, "digits")?,
+        None => 0
+    };
+
+    return Ok(match number(args, 0, "x")? {
+        Value::Int(value) if digits >= 0 => Value::Int(value),
+        Value::Int(value) => {
+            let factor = 10_i128.checked_pow(digits.unsigned_abs().min(u32::MAX as u64) as u32).unwrap_or(i128::MAX);
+            let value = value as i128;
+            let rounded = (value + value.signum() * (factor / 2)) / factor * factor;
+            i64::try_from(rounded).map(Value::Int).unwrap_or(Value::Float(rounded as f64))
+        },
+        value => {
+            let factor = 10_f64.powi(digits.clamp(-308, 308) as i32);
+            let rounded = (value.as_number().unwrap() * factor).round() / factor;
+            if digits <= 0 { whole(rounded) } else { finite(rounded) }
+        }
+    });
+}
+
+fn floor(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match number(args, 0, "x")? {
+        Value::Int(value) => Value::Int(value),
+        value => whole(value.as_number().unwrap().floor())
+    });
+}
+
+fn ceil(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match number(args, 0, "x")? {
+        Value::Int(value) => Value::Int(value),
+        value => whole(value.as_number().unwrap().ceil())
+    });
+}
+
+fn sqrt(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(finite(float(args, 0, "x")?.sqrt()));
+}
+
+fn pow(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return number(args, 0, "base")?.pow(&number(args, 1, "exponent")?);
+}
+
+// log(x[, base]), base 10 unless given
+fn log(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let base = match args.get(1) {
+        Some(_) => float(args, 1, "base")?,
+        None => 10.0
+    };
+
+    let x = float(args, 0, "x")?;
+    if x <= 0.0 || base <= 0.0 {
+        return Ok(Value::Null);
+    }
+
+    // The dedicated functions are exact for powers of their base, e.g. log(1000) is 3
+    return Ok(finite(match base {
+        10.0 => x.log10(),
+        2.0 => x.log2(),
+        base => x.ln() / base.ln()
+    }));
+}
+
+fn ln(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let x = float(args, 0, "x")?;
+    if x <= 0.0 {
+        return Ok(Value::Null);
+    }
+    return Ok(finite(x.ln()));
+}
+
+fn exp(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(finite(float(args, 0, "x")?.exp()));
+}
+
+// Smallest value, nulls are skipped
+fn min(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(flatten(args).into_iter()
+        .filter(|value| !value.is_null())
+        .min_by(|a, b| a.order(b))
+        .cloned()
+        .unwrap_or(Value::Null));
+}
+
+// Largest value, nulls are skipped
+fn max(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(flatten(args).into_iter()
+        .filter(|value| !value.is_null())
+        .max_by(|a, b| a.order(b))
+        .cloned()
+        .unwrap_or(Value::Null));
+}
+
+// clamp(x, low, high), x limited to the range low..=high
+fn clamp(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let x = number(args, 0, "x")?;
+    let low = number(args, 1, "low")?;
+    let high = number(args, 2, "high")?;
+
+    if low.order(&high).is_gt() {
+        return Err(format!("expects low to be at most high, got {} and {}", low, high).into());
+    }
+
+    if x.order(&low).is_lt() {
+        return Ok(low);
+    }
+    if x.order(&high).is_gt() {
+        return Ok(high);
+    }
+    return Ok(x);
+}
+
+thread_local! {
+    // Seeded from the SCRAP_SEED environment variable for repeatable results, otherwise from the clock
+    static RANDOM_STATE: Cell<u64> = Cell::new(
+        std::env::var("SCRAP_SEED").ok()
+            .and_then(|seed| seed.trim().parse().ok())
+            .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_nanos() as u64).unwrap_or(0))
+    );
+}
+
+// A whole number from 0 to 2^31 - 1 (splitmix64)
+fn random(_args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let state = RANDOM_STATE.with(|state| {
+        let next = state.get().wrapping_add(0x9E37_79B9_7F4A_7C15);
+        state.set(next);
+        next
+    });
+
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    return Ok(Value::Int((z >> 33) as i64));
+}
+
+// tonumber(x[, base]), base 10 unless given (2 to 36), text that isn't a number gives null
+fn tonumber(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let base = match args.get(1) {
+        Some(_) => integer(args, 1, "base")?,
+        None => 10
+    };
+
+    if !(2..=36).contains(&base) {
+        return Err(format!("expects a base from 2 to 36, got {}", base).into());
+    }
+
+    let text = args[0].to_string();
+    let text = text.trim();
+
+    if base == 10 {
+        return Ok(Value::parse_number(text).unwrap_or(Value::Null));
+    }
+
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.strip_prefix('+').unwrap_or(text))
+    };
+
+    // Allow the usual prefixes, e.g. tonumber("0xFF", 16)
+    let prefix = match base {
+        2 => Some("0b"),
+        8 => Some("0o"),
+        16 => Some("0x"),
+        _ => None
+    };
+    let digits = prefix
+        .and_then(|prefix| digits.strip_prefix(prefix).or_else(|| digits.strip_prefix(&prefix.to_uppercase())))
+        .unwrap_or(digits);
+
+    if digits.starts_with(['+', '-']) {
+        return Ok(Value::Null);
+    }
+
+    // Parse with the sign, so the smallest int still fits
+    let signed = if negative { format!("-{}", digits) } else { digits.to_string() };
+
+    return Ok(i64::from_str_radix(&signed, base as u32).map(Value::Int).unwrap_or(Value::Null));
+}
+
+// Inserts a comma between every group of three digits
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    return grouped;
+}
+
+// tostring(x[, format]), the format is one of
+// - "hex": whole numbers as 0x1F
+// - "commas": numbers with thousands separators, floats rounded to two decimals
+// - "duration": a number of seconds as [D+]HH:MM:SS
+fn tostring(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let format = match args.get(1) {
+        Some(format) => format.to_string(),
+        None => return Ok(Value::String(args[0].to_string()))
+    };
+
+    let sign = |negative: bool| if negative { "-" } else { "" };
+
+    let text = match format.as_str() {
+        "hex" => {
+            let value = integer(args, 0, "x")?;
+            format!("{}0x{:X}", sign(value < 0), value.unsigned_abs())
+        },
+        "commas" => match number(args, 0, "x")? {
+            Value::Int(value) => format!("{}{}", sign(value < 0), group_thousands(&value.unsigned_abs().to_string())),
+            value => {
+                let value = value.as_number().unwrap();
+                let text = format!("{:.2}", value.abs());
+                let (whole, fraction) = text.split_once('.').unwrap_or((&text, "00"));
+                format!("{}{}.{}", sign(value < 0.0), group_thousands(whole), fraction)
+            }
+        },
+        "duration" => {
+            let seconds = float(args, 0, "x")?.trunc();
+            let total = seconds.abs().min(u64::MAX as f64) as u64;
+            let (days, hours, minutes, seconds_left) = (total / 86_400, total / 3600 % 24, total / 60 % 60, total % 60);
+
+            let days = if days > 0 { format!("{}+", days) } else { String::new() };
+            format!("{}{}{:02}:{:02}:{:02}", sign(seconds < 0.0), days, hours, minutes, seconds_left)
+        },
+        _ => return Err(format!("expects hex, commas or duration as format, got \"{}\"", format).into())
+    };
+
+    return Ok(Value::String(text));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::FunctionResult;
+
+    fn call(f: fn(&[Value]) -> FunctionResult, args: &[Value]) -> Value {
+        return f(args).unwrap();
+    }
+
+    #[test]
+    fn rounds_away_from_zero() {
+        assert_eq!(call(round, &[Value::Float(2.5)]), Value::Int(3));
+        assert_eq!(call(round, &[Value::Float(-2.5)]), Value::Int(-3));
+        assert_eq!(call(round, &[Value::Float(1.2345), 2.into()]), Value::Float(1.23));
+        assert_eq!(call(round, &[1250.into(), (-2).into()]), Value::Int(1300));
+        assert_eq!(call(round, &[(-1250).into(), (-2).into()]), Value::Int(-1300));
+        assert_eq!(call(floor, &[Value::Float(-1.5)]), Value::Int(-2));
+        assert_eq!(call(ceil, &["1.2".into()]), Value::Int(2));
+        assert_eq!(call(abs, &[i64::MIN.into()]), Value::Float(9223372036854775808.0));
+    }
+
+    #[test]
+    fn gives_null_for_results_that_are_not_real() {
+        assert_eq!(call(sqrt, &[(-1).into()]), Value::Null);
+        assert_eq!(call(log, &[0.into()]), Value::Null);
+        assert_eq!(call(log, &[1000.into()]), Value::Float(3.0));
+        assert_eq!(call(log, &[8.into(), 2.into()]), Value::Float(3.0));
+        assert_eq!(call(ln, &[(-1).into()]), Value::Null);
+        assert_eq!(call(exp, &[1000.into()]), Value::Null);
+        assert_eq!(call(pow, &[2.into(), 10.into()]), Value::Int(1024));
+        assert!(sqrt(&["x".into()]).is_err());
+    }
+
+    #[test]
+    fn picks_and_limits_values() {
+        assert_eq!(call(min, &[3.into(), Value::Null, Value::Multivalue(vec![1.into(), 2.into()])]), Value::Int(1));
+        assert_eq!(call(max, &[3.into(), "10".into()]), Value::from("10"));
+        assert_eq!(call(max, &[Value::Null]), Value::Null);
+        assert_eq!(call(clamp, &[15.into(), 0.into(), 10.into()]), Value::Int(10));
+        assert_eq!(call(clamp, &[Value::Float(0.5), 0.into(), 10.into()]), Value::Float(0.5));
+        assert!(clamp(&[1.into(), 10.into(), 0.into()]).is_err());
+
+        let value = call(random, &[]).as_number().unwrap();
+        assert!((0.0..2_f64.powi(31)).contains(&value));
+    }
+
+    #[test]
+    fn converts_between_numbers_and_text() {
+        assert_eq!(call(tonumber, &["0xFF".into(), 16.into()]), Value::Int(255));
+        assert_eq!(call(tonumber, &["-101".into(), 2.into()]), Value::Int(-5));
+        assert_eq!(call(tonumber, &[" 2.5 ".into()]), Value::Float(2.5));
+        assert_eq!(call(tonumber, &["--1".into(), 16.into()]), Value::Null);
+        assert_eq!(call(tonumber, &["web".into()]), Value::Null);
+        assert!(tonumber(&["1".into(), 37.into()]).is_err());
+
+        assert_eq!(call(tostring, &[(-255).into(), "hex".into()]), Value::from("-0xFF"));
+        assert_eq!(call(tostring, &[1234567.into(), "commas".into()]), Value::from("1,234,567"));
+        assert_eq!(call(tostring, &[Value::Float(-1234.567), "commas".into()]), Value::from("-1,234.57"));
+        assert_eq!(call(tostring, &[90061.into(), "duration".into()]), Value::from("1+01:01:01"));
+        assert_eq!(call(tostring, &[Value::Float(1.5)]), Value::from("1.5"));
+        assert!(tostring(&[1.into(), "octal".into()]).is_err());
+    }
+}
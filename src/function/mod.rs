@@ -0,0 +1,203 @@
+// Functions callable from expressions, e.g. | eval n = len(name).
+// To add a new function, write its implementation and register it in REGISTRY,
+// or in the FUNCTIONS list of the module it belongs to.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::value::Value;
+
+mod conditional;
+mod json;
+mod math;
+mod multivalue;
+mod regex;
+mod string;
+mod time;
+
+type FunctionResult = Result<Value, Box<dyn Error>>;
+
+// The arguments of a call, evaluated when asked for
+pub trait Arguments {
+    fn count(&self) -> usize;
+
+    // Evaluates the argument with the given index
+    fn evaluate(&mut self, index: usize) -> FunctionResult;
+
+    // Evaluates the argument with a field set to another value, e.g. to every value of a multivalue
+    fn evaluate_with(&mut self, index: usize, field: &str, value: Value) -> FunctionResult;
+
+    // Names of the fields the argument refers to
+    fn fields(&self, index: usize) -> Vec<String>;
+
+    // Value of a field of the row, null if there is no such field
+    fn field(&self, name: &str) -> Value;
+}
+
+// Errors don't need to name the function, e.g. "expects a number as width" becomes "Function pad expects a number as width"
+enum Implementation {
+    // Called with every argument evaluated
+    Eager(fn(&[Value]) -> FunctionResult),
+    // Called with the arguments unevaluated, evaluating only the ones it needs (e.g. the taken branch of if)
+    Lazy(fn(&mut dyn Arguments) -> FunctionResult)
+}
+
+use Implementation::{Eager, Lazy};
+
+pub struct Function {
+    name: &'static str,
+    min_args: usize,
+    // None for functions taking any number of arguments
+    max_args: Option<usize>,
+    // Whether a null argument makes the result null without calling the implementation (eager functions only)
+    propagates_null: bool,
+    implementation: Implementation
+}
+
+const REGISTRY: &[Function] = &[
+    Function { name: "len", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(len) },
+    Function { name: "sum", min_args: 1, max_args: None, propagates_null: false, implementation: Eager(sum) },
+    Function { name: "count", min_args: 0, max_args: None, propagates_null: false, implementation: Eager(count) },
+];
+
+const MODULES: &[&[Function]] = &[
+    REGISTRY,
+    string::FUNCTIONS,
+    math::FUNCTIONS,
+    conditional::FUNCTIONS,
+    regex::FUNCTIONS,
+    time::FUNCTIONS,
+    multivalue::FUNCTIONS,
+    json::FUNCTIONS,
+];
+
+pub fn find(name: &str) -> Option<&'static Function> {
+    return MODULES.iter()
+        .flat_map(|functions| functions.iter())
+        .find(|function| function.name == name);
+}
+
+impl Function {
+    // Checks the number of arguments before calling the implementation
+    pub fn call(&self, arguments: &mut dyn Arguments) -> Result<Value, Box<dyn Error>> {
+        let count = arguments.count();
+        let expected = match self.max_args {
+            Some(max) if max == self.min_args => format!("{}", max),
+            Some(max) => format!("{} to {}", self.min_args, max),
+            None => format!("at least {}", self.min_args)
+        };
+
+        if count < self.min_args || self.max_args.is_some_and(|max| count > max) {
+            let plural = if expected == "1" || expected.ends_with(" 1") { "" } else { "s" };
+            return Err(format!("Function {} expects {} argument{}, got {}", self.name, expected, plural, count).into());
+        }
+
+        let result = match self.implementation {
+            Eager(implementation) => {
+                let args = (0..count).map(|index| arguments.evaluate(index)).collect::<Result<Vec<Value>, Box<dyn Error>>>()?;
+
+                if self.propagates_null && args.iter().any(Value::is_null) {
+                    return Ok(Value::Null);
+                }
+                implementation(&args)
+            },
+            Lazy(implementation) => implementation(arguments)
+        };
+
+        return result.map_err(|err| format!("Function {} {}", self.name, err).into());
+    }
+}
+
+// Conditions must be bools, null counts as false like in where
+fn truthy(value: Value, name: &str) -> Result<bool, Box<dyn Error>> {
+    return match value {
+        Value::Bool(result) => Ok(result),
+        Value::Null => Ok(false),
+        value => Err(format!("expects a bool as {}, got {} \"{}\"", name, value.type_name(), value).into())
+    };
+}
+
+// The argument as an int or float, strings are accepted when they hold one
+fn number(args: &[Value], index: usize, name: &str) -> Result<Value, Box<dyn Error>> {
+    let value = &args[index];
+    return value.to_number()
+        .ok_or(format!("expects a number as {}, got {} \"{}\"", name, value.type_name(), value).into());
+}
+
+// The argument as a whole number, floats and strings are accepted when they hold one
+fn integer(args: &[Value], index: usize, name: &str) -> Result<i64, Box<dyn Error>> {
+    let value = &args[index];
+
+    return match value.to_number() {
+        Some(Value::Int(number)) => Ok(number),
+        Some(Value::Float(number)) if number.fract() == 0.0 && number.abs() < i64::MAX as f64 => Ok(number as i64),
+        _ => Err(format!("expects a whole number as {}, got {} \"{}\"", name, value.type_name(), value).into())
+    };
+}
+
+// Arguments with multivalues spread into their values
+fn flatten(args: &[Value]) -> Vec<&Value> {
+    return args.iter()
+        .flat_map(|arg| match arg {
+            Value::Multivalue(values) => values.iter().collect(),
+            value => vec![value]
+        })
+        .collect();
+}
+
+// Number of characters in the text of the value
+fn len(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Int(args[0].to_string().chars().count() as i64));
+}
+
+// Sum of the numbers, nulls are skipped
+fn sum(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let mut total = Value::Int(0);
+
+    for (index, value) in flatten(args).into_iter().enumerate() {
+        if value.is_null() {
+            continue;
+        }
+
+        let number = value.to_number()
+            .ok_or(format!("expects numbers, got {} \"{}\" as value {}", value.type_name(), value, index + 1))?;
+        total = total.add(&number)?;
+    }
+
+    return Ok(total);
+}
+
+// Number of values that are not null
+fn count(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Int(flatten(args).iter().filter(|value| !value.is_null()).count() as i64));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::run;
+
+    #[test]
+    fn counts_characters_and_values() {
+        assert_eq!(len(&["héllo".into()]).unwrap(), Value::Int(5));
+        assert_eq!(len(&[Value::Int(-12)]).unwrap(), Value::Int(3));
+        assert_eq!(count(&[Value::Int(1), Value::Null, Value::Multivalue(vec!["a".into(), "b".into()])]).unwrap(), Value::Int(3));
+        assert_eq!(count(&[]).unwrap(), Value::Int(0));
+    }
+
+    #[test]
+    fn sums_numbers_and_skips_nulls() {
+        assert_eq!(sum(&[Value::Int(1), Value::Null, "2".into()]).unwrap(), Value::Int(3));
+        assert_eq!(sum(&[Value::Multivalue(vec![Value::Int(1), Value::Float(0.5)])]).unwrap(), Value::Float(1.5));
+        assert!(sum(&[Value::Int(1), "web".into()]).is_err());
+    }
+
+    #[test]
+    fn checks_the_number_of_arguments() {
+        let err = run("| generate count=1 | eval n = len(1, 2)").err().unwrap();
+        assert!(err.to_string().starts_with("Function len expects 1 argument, got 2"), "{}", err);
+        assert!(run("| generate count=1 | eval n = sum()").is_err());
+        assert!(run("| generate count=1 | eval n = nosuchfunction(1)").is_err());
+    }
+}
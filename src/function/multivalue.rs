@@ -0,0 +1,108 @@
+// Multivalue functions. A single value counts as a multivalue holding just that value.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{function::{integer, truthy, Arguments, Function, Implementation::{Eager, Lazy}}, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "mvcount", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(mvcount) },
+    Function { name: "mvindex", min_args: 2, max_args: Some(3), propagates_null: true, implementation: Eager(mvindex) },
+    Function { name: "mvjoin", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(mvjoin) },
+    Function { name: "mvfilter", min_args: 1, max_args: Some(1), propagates_null: false, implementation: Lazy(mvfilter) },
+];
+
+fn values(value: &Value) -> Vec<Value> {
+    return match value {
+        Value::Multivalue(values) => values.clone(),
+        Value::Null => Vec::new(),
+        value => vec![value.clone()]
+    };
+}
+
+// A single value stays a single value, no values at all are null
+fn collect(mut values: Vec<Value>) -> Value {
+    return match values.len() {
+        0 => Value::Null,
+        1 => values.remove(0),
+        _ => Value::Multivalue(values)
+    };
+}
+
+fn mvcount(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Int(values(&args[0]).len() as i64));
+}
+
+// mvindex(x, start[, end]), the values from start to end (both included), indexes start at 0 and count from the end when negative
+fn mvindex(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let values = values(&args[0]);
+    let count = values.len() as i64;
+
+    let position = |index: i64| if index < 0 { count + index } else { index };
+    let start = position(integer(args, 1, "start")?);
+    let end = match args.get(2) {
+        Some(_) => position(integer(args, 2, "end")?),
+        None => start
+    };
+
+    if start < 0 || end >= count || start > end {
+        return Ok(Value::Null);
+    }
+
+    return Ok(collect(values[start as usize..=end as usize].to_vec()));
+}
+
+fn mvjoin(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let separator = args[1].to_string();
+    return Ok(Value::String(values(&args[0]).iter().map(|value| value.to_string()).collect::<Vec<String>>().join(&separator)));
+}
+
+// mvfilter(condition), the values of a multivalue field for which the condition holds, e.g. mvfilter(tags != "debug").
+// The condition must refer to a single field, which stands for each of its values in turn.
+fn mvfilter(args: &mut dyn Arguments) -> Result<Value, Box<dyn Error>> {
+    let field = match args.fields(0).as_slice() {
+        [field] => field.clone(),
+        fields => return Err(format!("expects a condition on a single field, got {} fields", fields.len()).into())
+    };
+
+    let mut kept = Vec::new();
+    for value in values(&args.field(&field)) {
+        if truthy(args.evaluate_with(0, &field, value.clone())?, "condition")? {
+            kept.push(value);
+        }
+    }
+
+    return Ok(collect(kept));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{column, run};
+
+    fn tags() -> Value {
+        return Value::Multivalue(vec!["web".into(), "prod".into(), "eu".into()]);
+    }
+
+    #[test]
+    fn counts_and_indexes_values() {
+        assert_eq!(mvcount(&[tags()]).unwrap(), Value::Int(3));
+        assert_eq!(mvcount(&["single".into()]).unwrap(), Value::Int(1));
+        assert_eq!(mvindex(&[tags(), (-1).into()]).unwrap(), Value::from("eu"));
+        assert_eq!(mvindex(&[tags(), 0.into(), 1.into()]).unwrap(), Value::Multivalue(vec!["web".into(), "prod".into()]));
+        assert_eq!(mvindex(&[tags(), 3.into()]).unwrap(), Value::Null);
+        assert_eq!(mvindex(&[tags(), 2.into(), 1.into()]).unwrap(), Value::Null);
+        assert_eq!(mvjoin(&[tags(), "/".into()]).unwrap(), Value::from("web/prod/eu"));
+    }
+
+    #[test]
+    fn filters_values_and_compares_any_of_them() {
+        let table = run("| generate count=1 | eval tags = split(\"web,prod,eu\", \",\") | eval kept = mvfilter(tags != \"prod\") | eval none = mvfilter(tags == \"x\") | eval one = mvfilter(len(tags) == 4) | where tags == \"eu\"").unwrap();
+        assert_eq!(column(&table, "kept"), [Value::Multivalue(vec!["web".into(), "eu".into()])]);
+        assert_eq!(column(&table, "none"), [Value::Null]);
+        assert_eq!(column(&table, "one"), [Value::from("prod")]);
+
+        assert!(run("| generate count=1 | eval a = 1 | eval b = 2 | eval c = mvfilter(a == b)").is_err());
+    }
+}
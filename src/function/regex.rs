@@ -0,0 +1,83 @@
+// Regular expression functions, working on the text of the value.
+// Patterns are compiled once per query, see crate::pattern.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{function::{Function, Implementation::Eager}, pattern, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "match", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(match_) },
+    Function { name: "replace_regex", min_args: 3, max_args: Some(3), propagates_null: true, implementation: Eager(replace_regex) },
+    Function { name: "extract", min_args: 2, max_args: Some(3), propagates_null: true, implementation: Eager(extract) },
+];
+
+// match(x, pattern), whether the pattern matches anywhere in x
+fn match_(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Bool(pattern::compile(&args[1].to_string())?.is_match(&args[0].to_string())));
+}
+
+// replace_regex(x, pattern, replacement), the replacement can refer to groups as $1 or ${name}
+fn replace_regex(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let regex = pattern::compile(&args[1].to_string())?;
+    return Ok(Value::String(regex.replace_all(&args[0].to_string(), args[2].to_string().as_str()).into_owned()));
+}
+
+// extract(x, pattern[, group]), the text of the group (a number or a name) in the first match, or null when nothing matches.
+// Without a group, it's the first group, or the whole match for patterns without groups.
+fn extract(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let regex = pattern::compile(&args[1].to_string())?;
+
+    let group = match args.get(2) {
+        Some(Value::Int(index)) => match usize::try_from(*index) {
+            Ok(index) if index < regex.captures_len() => index,
+            _ => return Err(format!("expects a group from 0 to {}, got {}", regex.captures_len() - 1, index).into())
+        },
+        Some(name) => {
+            let name = name.to_string();
+            regex.capture_names()
+                .position(|group| group == Some(name.as_str()))
+                .ok_or(format!("expects a group of the pattern, got \"{}\"", name))?
+        },
+        None => if regex.captures_len() > 1 { 1 } else { 0 }
+    };
+
+    return Ok(regex.captures(&args[0].to_string())
+        .and_then(|captures| captures.get(group))
+        .map(|group| Value::from(group.as_str()))
+        .unwrap_or(Value::Null));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter;
+
+    #[test]
+    fn matches_and_replaces() {
+        assert_eq!(match_(&["id=42".into(), r"id=\d+".into()]).unwrap(), Value::Bool(true));
+        assert_eq!(match_(&["id=x".into(), r"^id=\d+$".into()]).unwrap(), Value::Bool(false));
+        assert_eq!(replace_regex(&["a1b22".into(), r"\d+".into(), "#".into()]).unwrap(), Value::from("a#b#"));
+        assert_eq!(replace_regex(&["john smith".into(), r"(?<first>\w+) (\w+)".into(), "$2, ${first}".into()]).unwrap(), Value::from("smith, john"));
+        assert!(match_(&["x".into(), "(".into()]).is_err());
+    }
+
+    #[test]
+    fn extracts_groups() {
+        let text = Value::from("user=ann id=7");
+        assert_eq!(extract(&[text.clone(), r"user=(\w+)".into()]).unwrap(), Value::from("ann"));
+        assert_eq!(extract(&[text.clone(), r"id=\d".into()]).unwrap(), Value::from("id=7"));
+        assert_eq!(extract(&[text.clone(), r"(?<key>\w+)=(?<value>\w+)".into(), "value".into()]).unwrap(), Value::from("ann"));
+        assert_eq!(extract(&[text.clone(), r"(\w+)=(\w+)".into(), 2.into()]).unwrap(), Value::from("ann"));
+        assert_eq!(extract(&[text.clone(), r"host=(\w+)".into()]).unwrap(), Value::Null);
+        assert!(extract(&[text, r"(\w+)".into(), "missing".into()]).is_err());
+    }
+
+    #[test]
+    fn evaluates_the_match_operators() {
+        assert_eq!(interpreter::evaluate(r#""id=42" =~ "id=\d+""#).unwrap(), Value::Bool(true));
+        assert_eq!(interpreter::evaluate(r#""id=42" !~ "id=\d+""#).unwrap(), Value::Bool(false));
+        assert_eq!(interpreter::evaluate(r#"null =~ ".*""#).unwrap(), Value::Bool(false));
+    }
+}
@@ -0,0 +1,281 @@
+// String functions. They work on characters rather than bytes, and on the text of non-string values (e.g. upper(5) is "5").
+// Functions that transform text apply to every value of a multivalue.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{function::{integer, Function, Implementation::Eager}, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "lower", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(lower) },
+    Function { name: "upper", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(upper) },
+    Function { name: "trim", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(trim) },
+    Function { name: "ltrim", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(ltrim) },
+    Function { name: "rtrim", min_args: 1, max_args: Some(2), propagates_null: true, implementation: Eager(rtrim) },
+    Function { name: "substr", min_args: 2, max_args: Some(3), propagates_null: true, implementation: Eager(substr) },
+    Function { name: "replace", min_args: 3, max_args: Some(3), propagates_null: true, implementation: Eager(replace) },
+    Function { name: "split", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(split) },
+    Function { name: "join", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(join) },
+    Function { name: "contains", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(contains) },
+    Function { name: "startswith", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(startswith) },
+    Function { name: "endswith", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(endswith) },
+    Function { name: "pad", min_args: 2, max_args: Some(4), propagates_null: true, implementation: Eager(pad) },
+    Function { name: "repeat", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(repeat) },
+    Function { name: "reverse", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(reverse) },
+    Function { name: "urldecode", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(urldecode) },
+    Function { name: "urlencode", min_args: 1, max_args: Some(1), propagates_null: true, implementation: Eager(urlencode) },
+];
+
+// Longest text pad and repeat produce, in characters
+const MAX_LENGTH: usize = 1 << 20;
+
+// Applies the function to the text of the value, or of every value in a multivalue
+fn map_text(value: &Value, f: &dyn Fn(&str) -> String) -> Value {
+    return match value {
+        Value::Multivalue(values) => Value::Multivalue(values.iter().map(|value| map_text(value, f)).collect()),
+        value => Value::String(f(&value.to_string()))
+    };
+}
+
+fn lower(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(map_text(&args[0], &|text| text.to_lowercase()));
+}
+
+fn upper(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(map_text(&args[0], &|text| text.to_uppercase()));
+}
+
+// The characters to trim, whitespace unless given
+fn trim_chars(args: &[Value]) -> Option<Vec<char>> {
+    return args.get(1).map(|chars| chars.to_string().chars().collect());
+}
+
+fn trim(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match trim_chars(args) {
+        Some(chars) => map_text(&args[0], &|text| text.trim_matches(chars.as_slice()).to_string()),
+        None => map_text(&args[0], &|text| text.trim().to_string())
+    });
+}
+
+fn ltrim(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match trim_chars(args) {
+        Some(chars) => map_text(&args[0], &|text| text.trim_start_matches(chars.as_slice()).to_string()),
+        None => map_text(&args[0], &|text| text.trim_start().to_string())
+    });
+}
+
+fn rtrim(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match trim_chars(args) {
+        Some(chars) => map_text(&args[0], &|text| text.trim_end_matches(chars.as_slice()).to_string()),
+        None => map_text(&args[0], &|text| text.trim_end().to_string())
+    });
+}
+
+// substr(x, start[, length]), start is 1-based and counts from the end when negative
+fn substr(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let chars: Vec<char> = args[0].to_string().chars().collect();
+    let start = integer(args, 1, "start")?;
+    let length = match args.get(2) {
+        Some(_) => integer(args, 2, "length")?,
+        None => i64::MAX
+    };
+
+    if length < 0 {
+        return Err(format!("expects a length of at least 0, got {}", length).into());
+    }
+
+    let count = chars.len() as i64;
+    let start = match start {
+        0 => 0,
+        start if start > 0 => start - 1,
+        start => (count + start).max(0)
+    }.min(count);
+    let end = start.saturating_add(length).min(count);
+
+    return Ok(Value::String(chars[start as usize..end as usize].iter().collect()));
+}
+
+// Replaces every occurrence of the text, not a pattern
+fn replace(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let from = args[1].to_string();
+    let to = args[2].to_string();
+
+    if from.is_empty() {
+        return Err("expects text to replace, got an empty string".into());
+    }
+
+    return Ok(map_text(&args[0], &|text| text.replace(&from, &to)));
+}
+
+// An empty separator splits into characters
+fn split(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let text = args[0].to_string();
+    let separator = args[1].to_string();
+
+    let values = if separator.is_empty() {
+        text.chars().map(|c| Value::String(c.to_string())).collect()
+    } else {
+        text.split(separator.as_str()).map(Value::from).collect()
+    };
+
+    return Ok(Value::Multivalue(values));
+}
+
+fn join(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let separator = args[1].to_string();
+
+    return Ok(Value::String(match &args[0] {
+        Value::Multivalue(values) => values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(&separator),
+        value => value.to_string()
+    }));
+}
+
+fn contains(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Bool(args[0].to_string().contains(&args[1].to_string())));
+}
+
+fn startswith(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Bool(args[0].to_string().starts_with(&args[1].to_string())));
+}
+
+fn endswith(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Bool(args[0].to_string().ends_with(&args[1].to_string())));
+}
+
+// pad(x, width[, fill[, side]]), pads the start ("left", the default) or the end ("right") up to width characters
+fn pad(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let text = args[0].to_string();
+    let width = integer(args, 1, "width")?;
+
+    let fill = args.get(2).map(|fill| fill.to_string()).unwrap_or(String::from(" "));
+    let mut fill_chars = fill.chars();
+    let fill = match (fill_chars.next(), fill_chars.next()) {
+        (Some(fill), None) => fill,
+        _ => return Err(format!("expects a single character to pad with, got \"{}\"", fill).into())
+    };
+
+    let left = match args.get(3).map(|side| side.to_string()).as_deref() {
+        None | Some("left") => true,
+        Some("right") => false,
+        Some(side) => return Err(format!("expects left or right as side, got \"{}\"", side).into())
+    };
+
+    if width > MAX_LENGTH as i64 {
+        return Err(format!("expects a width of at most {}, got {}", MAX_LENGTH, width).into());
+    }
+
+    let missing = (width.max(0) as usize).saturating_sub(text.chars().count());
+    let padding: String = std::iter::repeat_n(fill, missing).collect();
+
+    return Ok(Value::String(if left { padding + &text } else { text + &padding }));
+}
+
+fn repeat(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    let count = integer(args, 1, "count")?;
+
+    if count < 0 {
+        return Err(format!("expects a count of at least 0, got {}", count).into());
+    }
+
+    let text = args[0].to_string();
+    if (count as usize).checked_mul(text.chars().count()).is_none_or(|length| length > MAX_LENGTH) {
+        return Err(format!("expects a result of at most {} characters, got {} times {} characters", MAX_LENGTH, count, text.chars().count()).into());
+    }
+
+    return Ok(Value::String(text.repeat(count as usize)));
+}
+
+// Reverses the characters, or the order of the values of a multivalue
+fn reverse(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(match &args[0] {
+        Value::Multivalue(values) => Value::Multivalue(values.iter().rev().cloned().collect()),
+        value => Value::String(value.to_string().chars().rev().collect())
+    });
+}
+
+// Decodes %XX escapes and '+' as a space, invalid escapes are kept as they are
+fn urldecode(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(map_text(&args[0], &|text| {
+        let bytes = text.as_bytes();
+        let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+        let mut index = 0;
+
+        while index < bytes.len() {
+            // from_str_radix accepts a sign, so %+5 would decode to 0x05
+            let escaped = bytes.get(index + 1..index + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+            match (bytes[index], escaped) {
+                (b'%', Some(byte)) => {
+                    decoded.push(byte);
+                    index += 3;
+                },
+                (b'+', _) => {
+                    decoded.push(b' ');
+                    index += 1;
+                },
+                (byte, _) => {
+                    decoded.push(byte);
+                    index += 1;
+                }
+            }
+        }
+
+        return String::from_utf8_lossy(&decoded).into_owned();
+    }));
+}
+
+// Escapes everything except letters, digits and -_.~ as UTF-8 %XX sequences
+fn urlencode(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(map_text(&args[0], &|text| {
+        return text.bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+                byte => format!("%{:02X}", byte)
+            })
+            .collect();
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::FunctionResult;
+
+    fn call(f: fn(&[Value]) -> FunctionResult, args: &[Value]) -> Value {
+        return f(args).unwrap();
+    }
+
+    #[test]
+    fn decodes_only_valid_escapes() {
+        assert_eq!(call(urldecode, &["a%20b+c%C3%A9".into()]), "a b cé".into());
+        assert_eq!(call(urldecode, &["%+5%-1%zz%4".into()]), "% 5%-1%zz%4".into());
+        assert_eq!(call(urlencode, &["a b/é".into()]), "a%20b%2F%C3%A9".into());
+    }
+
+    #[test]
+    fn pads_and_repeats_up_to_a_limit() {
+        assert_eq!(call(pad, &["7".into(), 3.into(), "0".into()]), "007".into());
+        assert_eq!(call(pad, &["é".into(), 3.into(), "-".into(), "right".into()]), "é--".into());
+        assert_eq!(call(repeat, &["ab".into(), 3.into()]), "ababab".into());
+        assert_eq!(call(repeat, &["".into(), i64::MAX.into()]), "".into());
+
+        assert!(pad(&["x".into(), (MAX_LENGTH as i64 + 1).into()]).is_err());
+        assert!(repeat(&["ab".into(), (MAX_LENGTH as i64).into()]).is_err());
+        assert!(repeat(&["x".into(), i64::MAX.into()]).is_err());
+        assert!(repeat(&["x".into(), (-1).into()]).is_err());
+    }
+
+    #[test]
+    fn works_on_characters() {
+        assert_eq!(call(substr, &["héllo".into(), (-3).into(), 2.into()]), "ll".into());
+        assert_eq!(call(substr, &["héllo".into(), 2.into()]), "éllo".into());
+        assert_eq!(call(reverse, &["abé".into()]), "éba".into());
+        assert_eq!(call(split, &["a,b".into(), ",".into()]), Value::Multivalue(vec!["a".into(), "b".into()]));
+        assert_eq!(call(upper, &[Value::Multivalue(vec!["a".into(), 5.into()])]), Value::Multivalue(vec!["A".into(), "5".into()]));
+        assert_eq!(call(trim, &["xxaxx".into(), "x".into()]), "a".into());
+    }
+}
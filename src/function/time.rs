@@ -0,0 +1,79 @@
+// Time functions. Timestamps are in UTC, and numbers are accepted as epoch seconds wherever a timestamp is expected.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::{function::{Function, Implementation::Eager}, time, value::Value};
+
+pub const FUNCTIONS: &[Function] = &[
+    Function { name: "now", min_args: 0, max_args: Some(0), propagates_null: false, implementation: Eager(now) },
+    Function { name: "strptime", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(strptime) },
+    Function { name: "strftime", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(strftime) },
+    Function { name: "relative_time", min_args: 2, max_args: Some(2), propagates_null: true, implementation: Eager(relative_time) },
+];
+
+// The argument as a timestamp, numbers (and strings holding one) are epoch seconds
+fn timestamp(args: &[Value], index: usize, name: &str) -> Result<i64, Box<dyn Error>> {
+    let value = &args[index];
+
+    if let Value::Timestamp(timestamp) = value {
+        return Ok(*timestamp);
+    }
+
+    return value.as_number()
+        .and_then(time::from_seconds)
+        .ok_or(format!("expects a timestamp or epoch seconds as {}, got {} \"{}\"", name, value.type_name(), value).into());
+}
+
+// The time the query started, the same for every row
+fn now(_args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Timestamp(time::now()));
+}
+
+// strptime(x, format), null when x doesn't match the format
+fn strptime(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    if let Value::Timestamp(_) = args[0] {
+        return Ok(args[0].clone());
+    }
+    return Ok(time::strptime(&args[0].to_string(), &args[1].to_string()).map(Value::Timestamp).unwrap_or(Value::Null));
+}
+
+// strftime(t, format)
+fn strftime(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::String(time::strftime(timestamp(args, 0, "time")?, &args[1].to_string())));
+}
+
+// relative_time(t, specifier), e.g. relative_time(now(), "-1d@d") is the start of yesterday
+fn relative_time(args: &[Value]) -> Result<Value, Box<dyn Error>> {
+    return Ok(Value::Timestamp(time::relative_time(timestamp(args, 0, "time")?, &args[1].to_string())?));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::run;
+
+    #[test]
+    fn accepts_timestamps_and_epoch_seconds() {
+        let format = Value::from("%Y-%m-%d %H:%M:%S");
+        assert_eq!(strftime(&[Value::Timestamp(1_700_000_000_000), format.clone()]).unwrap(), Value::from("2023-11-14 22:13:20"));
+        assert_eq!(strftime(&[1_700_000_000.into(), format.clone()]).unwrap(), Value::from("2023-11-14 22:13:20"));
+        assert_eq!(strftime(&["1700000000.5".into(), "%S.%3N".into()]).unwrap(), Value::from("20.500"));
+        assert!(strftime(&["yesterday".into(), format]).is_err());
+    }
+
+    #[test]
+    fn parses_and_shifts_timestamps() {
+        assert_eq!(strptime(&["2024-03-01 12:30".into(), "%Y-%m-%d %H:%M".into()]).unwrap(), Value::Timestamp(1_709_296_200_000));
+        assert_eq!(strptime(&["12:30".into(), "%Y-%m-%d".into()]).unwrap(), Value::Null);
+        assert_eq!(relative_time(&[Value::Timestamp(1_709_296_200_000), "-1d@d".into()]).unwrap(), Value::Timestamp(1_709_164_800_000));
+        assert!(relative_time(&[Value::Timestamp(0), "yesterday".into()]).is_err());
+    }
+
+    #[test]
+    fn reads_the_clock_once_per_query() {
+        let table = run("| generate count=50 | eval t = now() | dedup t").unwrap();
+        assert_eq!(table.row_count(), 1);
+    }
+}
@@ -1,3 +1,4 @@
+#![allow(clippy::needless_return)]
 
 
 // pub fn evaluate_command(command: &Command, state: &mut Table) {
@@ -33,9 +34,9 @@
 
 */
 
-use std::{error::Error, fs::File, io::{self, BufReader, Read, Write}, os::linux::raw::stat};
+use std::{error::Error, fs::File, io::{self, BufReader, Read, Write}};
 
-use crate::{command::{self, CommandExecutor}, interpreter, output::Table, parser::{parse, ParseNode, ParseNodeType}, scanner::{scan, CommandKeyword, Token, TokenType}};
+use crate::{command::CommandExecutor, output::Table, pattern, time, parser::{parse, ParseNode, ParseNodeType}, scanner::{scan, CommandKeyword, Token, TokenType}};
 
 
 struct Interpreter {
@@ -47,45 +48,6 @@ impl Interpreter {
         return Interpreter { output: Table::new() };
     }
 
-    pub fn execute_value(&mut self, value: &ParseNode) -> Result<(), Box<dyn Error>> {
-        return Ok(());
-    }
-
-    pub fn execute_expression(&mut self, expression: &ParseNode) -> Result<(), Box<dyn Error>> {
-        let children = expression.children();
-
-        let lvalue = if matches!(&children[0].variant, ParseNodeType::Expression) {
-            self.execute_expression(&children[0])?
-        } else {
-            self.execute_value(&children[0])?
-        };
-        
-        let operator = &children[1];
-
-        let rvalue = if matches!(&children[2].variant, ParseNodeType::Expression) {
-            self.execute_expression(&children[2])?
-        } else {
-            self.execute_value(&children[2])?
-        };
-
-        return Ok(());
-    }
-
-    pub fn execute_assignment(&mut self, argument: &ParseNode) -> Result<(), Box<dyn Error>> {
-        let identifier = argument.child(0).unwrap().token_value().unwrap();
-
-        return Ok(());
-    }
-
-    // Substitution and reduction happens here
-    pub fn execute_argument(&mut self, argument: &ParseNode) -> Result<(), Box<dyn Error>> {
-        // Is it an assignment?
-        if matches!(argument.variant, ParseNodeType::Assignment) {
-            return Ok(self.execute_assignment(argument)?);
-        }
-        return Ok(());
-    }
-
     pub fn execute_command(&mut self, command_tree: &ParseNode) -> Result<(), Box<dyn Error>> {        
         // Check if parse node is a command
         if !matches!(command_tree.variant, ParseNodeType::Command) {
@@ -105,10 +67,31 @@ impl Interpreter {
 
         let command = &token.token_type;
 
-        let _ = match command {
+        match command {
             TokenType::CommandKeyword(CommandKeyword::Generate) => crate::command::generate::Generate::execute(args, &mut self.output)?,
             TokenType::CommandKeyword(CommandKeyword::Eval) => crate::command::eval::Eval::execute(args, &mut self.output)?,
             TokenType::CommandKeyword(CommandKeyword::Where) => crate::command::r#where::Where::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Rename) => crate::command::rename::Rename::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Remove) => crate::command::remove::Remove::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Top) => crate::command::top::Top::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Stats) => crate::command::stats::Stats::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Sort) => crate::command::sort::Sort::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Head) => crate::command::head::Head::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Tail) => crate::command::tail::Tail::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Dedup) => crate::command::dedup::Dedup::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Table) => crate::command::table::Table::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Fields) => crate::command::fields::Fields::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Rex) => crate::command::rex::Rex::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Bin) => crate::command::bin::Bin::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Makemv) => crate::command::makemv::Makemv::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Mvexpand) => crate::command::mvexpand::Mvexpand::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Nomv) => crate::command::nomv::Nomv::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Inputcsv) => crate::command::inputcsv::Inputcsv::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Outputcsv) => crate::command::outputcsv::Outputcsv::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Outputjson) => crate::command::outputjson::Outputjson::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Inputjson) => crate::command::inputjson::Inputjson::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Spath) => crate::command::spath::Spath::execute(args, &mut self.output)?,
+            TokenType::CommandKeyword(CommandKeyword::Kv) => crate::command::kv::Kv::execute(args, &mut self.output)?,
             _ => return Ok(())
         };
 
@@ -140,7 +123,11 @@ impl Interpreter {
         }
 
         let children = parse_tree.children.as_ref().unwrap();
-        
+
+        // Patterns are compiled and the clock is read once per query
+        pattern::clear();
+        time::reset_clock();
+
         for child in children {
             self.execute_statement(child)?;
         }
@@ -176,6 +163,28 @@ pub(crate) fn run_file(file_path: &str) -> Result<(), Box<dyn Error>> {
     return Ok(());
 }
 
+// The table a query leaves behind
+#[cfg(test)]
+pub(crate) fn run(query: &str) -> Result<Table, Box<dyn Error>> {
+    let mut interpreter = Interpreter::new();
+    interpreter.execute(parse(&scan(query))?)?;
+    return Ok(interpreter.output);
+}
+
+// The value of the expression in a table with a single row
+#[cfg(test)]
+pub(crate) fn evaluate(expression: &str) -> Result<crate::value::Value, Box<dyn Error>> {
+    let table = run(&format!("| generate count=1 | eval result = {}", expression))?;
+    return Ok(column(&table, "result").remove(0));
+}
+
+// The values of a column, from the first row to the last
+#[cfg(test)]
+pub(crate) fn column(table: &Table, name: &str) -> Vec<crate::value::Value> {
+    let index = table.get_column(name).unwrap();
+    return table.rows().iter().map(|row| row[index].clone()).collect();
+}
+
 pub(crate) fn repl() -> Result<(), Box<dyn Error>> {
     let mut interpreter = Interpreter::new();
 
@@ -206,7 +215,7 @@ pub(crate) fn repl() -> Result<(), Box<dyn Error>> {
             break;
         }
 
-        if data == "" {
+        if data.is_empty() {
             continue;
         }
 
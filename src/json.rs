@@ -0,0 +1,190 @@
+// JSON documents as table values. Nested objects are flattened into dotted names, e.g. {"user": {"id": 7}} is user.id,
+// and arrays become multivalues.
+
+#![allow(clippy::needless_return)]
+
+use std::error::Error;
+
+use crate::value::Value;
+
+// Adds every leaf of the value to the fields, named after the path leading to it
+pub fn flatten(value: &serde_json::Value, name: &str, fields: &mut Vec<(String, Value)>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                let name = if name.is_empty() { key.clone() } else { format!("{}.{}", name, key) };
+                flatten(value, &name, fields);
+            }
+        },
+        value => match fields.iter_mut().find(|(field, _)| field == name) {
+            Some((_, existing)) => *existing = to_value(value),
+            None => fields.push((name.to_string(), to_value(value)))
+        }
+    }
+}
+
+// Objects inside arrays are kept as JSON text, an empty array is null
+pub fn to_value(value: &serde_json::Value) -> Value {
+    return match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(value) => Value::Bool(*value),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(number) => Value::Int(number),
+            None => number.as_f64().map(Value::Float).unwrap_or(Value::Null)
+        },
+        serde_json::Value::String(value) => Value::String(value.clone()),
+        serde_json::Value::Array(values) if values.is_empty() => Value::Null,
+        serde_json::Value::Array(values) => Value::Multivalue(values.iter().map(|value| match value {
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => Value::String(value.to_string()),
+            value => to_value(value)
+        }).collect()),
+        serde_json::Value::Object(_) => Value::String(value.to_string())
+    };
+}
+
+// Line of the byte offset in the text, starting at 1
+pub fn line_at(text: &str, offset: usize) -> usize {
+    return text[..offset.min(text.len())].matches('\n').count() + 1;
+}
+
+// Offset of the first character at or after the offset that isn't whitespace
+pub fn skip_whitespace(text: &str, offset: usize) -> usize {
+    let rest = &text[offset.min(text.len())..];
+    return text.len() - rest.trim_start().len();
+}
+
+// A parse error as "<path> line <line> column <column>: <message>"
+pub fn error(path: &str, err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    let location = format!(" at line {} column {}", err.line(), err.column());
+    return format!("{} line {} column {}: {}", path, err.line(), err.column(), message.strip_suffix(&location).unwrap_or(&message));
+}
+
+pub enum Step {
+    Key(String),
+    // Counts from the end when negative
+    Index(i64)
+}
+
+// Parses a path such as request.headers.host, items[0].id or $.items[-1]["content-type"], the leading $ is optional
+pub fn parse_path(path: &str) -> Result<Vec<Step>, Box<dyn Error>> {
+    let invalid = || format!("Invalid JSON path \"{}\"", path);
+
+    let mut steps: Vec<Step> = Vec::new();
+    let mut chars = path.strip_prefix('$').unwrap_or(path).chars().peekable();
+    let mut expects_key = !path.starts_with('$');
+
+    while let Some(c) = chars.peek().copied() {
+        match c {
+            '.' if !expects_key => {
+                chars.next();
+                expects_key = true;
+            },
+            '[' => {
+                chars.next();
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(invalid().into())
+                    }
+                }
+
+                let quoted = ['"', '\''].iter().find(|quote| inner.len() >= 2 && inner.starts_with(**quote) && inner.ends_with(**quote));
+                steps.push(match quoted {
+                    Some(_) => Step::Key(inner[1..inner.len() - 1].to_string()),
+                    None => Step::Index(inner.trim().parse().map_err(|_| invalid())?)
+                });
+                expects_key = false;
+            },
+            _ if expects_key => {
+                let mut key = String::new();
+                while let Some(c) = chars.next_if(|c| *c != '.' && *c != '[') {
+                    key.push(c);
+                }
+                if key.is_empty() {
+                    return Err(invalid().into());
+                }
+                steps.push(Step::Key(key));
+                expects_key = false;
+            },
+            _ => return Err(invalid().into())
+        }
+    }
+
+    if expects_key && !steps.is_empty() {
+        return Err(invalid().into());
+    }
+
+    return Ok(steps);
+}
+
+// The part of the value at the end of the path, if there is one
+pub fn select<'a>(value: &'a serde_json::Value, steps: &[Step]) -> Option<&'a serde_json::Value> {
+    let mut value = value;
+
+    for step in steps {
+        value = match (step, value) {
+            (Step::Key(key), serde_json::Value::Object(object)) => object.get(key)?,
+            (Step::Index(index), serde_json::Value::Array(values)) => {
+                let index = if *index < 0 { values.len() as i64 + index } else { *index };
+                values.get(usize::try_from(index).ok()?)?
+            },
+            _ => return None
+        };
+    }
+
+    return Some(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> serde_json::Value {
+        return serde_json::from_str(r#"{"user": {"id": 7, "tags": ["a", {"b": 1}]}, "items": [3, 4], "content-type": "text", "empty": []}"#).unwrap();
+    }
+
+    #[test]
+    fn flattens_objects_into_dotted_names() {
+        let mut fields = Vec::new();
+        flatten(&document(), "", &mut fields);
+
+        assert_eq!(fields, [
+            ("user.id".to_string(), Value::Int(7)),
+            ("user.tags".to_string(), Value::Multivalue(vec!["a".into(), r#"{"b":1}"#.into()])),
+            ("items".to_string(), Value::Multivalue(vec![Value::Int(3), Value::Int(4)])),
+            ("content-type".to_string(), "text".into()),
+            ("empty".to_string(), Value::Null)
+        ]);
+    }
+
+    #[test]
+    fn selects_values_along_a_path() {
+        let document = document();
+        let at = |path: &str| select(&document, &parse_path(path).unwrap()).cloned();
+
+        assert_eq!(at("user.id"), Some(serde_json::json!(7)));
+        assert_eq!(at("$.items[-1]"), Some(serde_json::json!(4)));
+        assert_eq!(at("$[\"content-type\"]"), Some(serde_json::json!("text")));
+        assert_eq!(at("user.tags[1].b"), Some(serde_json::json!(1)));
+        assert_eq!(at("items[2]"), None);
+        assert_eq!(at("user.id.x"), None);
+        assert_eq!(at("$"), Some(document.clone()));
+
+        for path in ["user.", "items[", "items[x]", "a..b", ".a"] {
+            assert!(parse_path(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn finds_lines_and_skips_whitespace() {
+        let text = "{}\n\n  {\n}";
+        assert_eq!(skip_whitespace(text, 2), 6);
+        assert_eq!(line_at(text, 6), 3);
+        assert_eq!(line_at(text, 0), 1);
+        assert_eq!(line_at(text, 100), 4);
+        assert_eq!(skip_whitespace(text, 100), text.len());
+    }
+}
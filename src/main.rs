@@ -3,6 +3,14 @@ mod scanner;
 mod parser;
 mod command;
 mod output;
+mod aggregate;
+mod function;
+mod wildcard;
+mod pattern;
+mod time;
+mod csv;
+mod json;
+mod value;
 
 use std::env;
 
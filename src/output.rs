@@ -1,79 +1,119 @@
+#![allow(clippy::needless_return)]
+
 use std::{collections::HashMap, error::Error, iter::zip};
 
-use crate::parser::ParseNode;
+use crate::value::Value;
+
+// Placeholder shown in cells without a value
+pub const EMPTY: &str = "<Empty>";
 
 pub struct Table {
-    cells: Vec<Vec<String>>, // Rows -> Cols
-    max_width: Vec<usize>
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>> // Rows -> Cols
 }
 
 impl Table {
     pub fn new() -> Self {
         Self {
-            cells: vec![vec![]],
-            max_width: vec![]
+            columns: vec![],
+            rows: vec![]
         }
     }
 
     pub fn clear(&mut self) {
-        self.cells.clear();
-        self.max_width.clear();
+        self.columns.clear();
+        self.rows.clear();
     }
 
     pub fn row_count(&self) -> usize {
-        return self.cells.len();
+        return self.rows.len();
     }
 
+    #[allow(dead_code)]
     pub fn col_count(&self) -> usize {
-        return self.cells[0].len();
+        return self.columns.len();
     }
 
-    pub fn set_cell(&mut self, column: usize, row: usize, value: &String) -> Result<(), Box<dyn Error>> {
-        self.cells[row][column] = value.clone();
-        if value.len() > self.max_width[column] {
-            self.max_width[column] = value.len();
-        }
+    pub fn set_cell(&mut self, column: usize, row: usize, value: Value) -> Result<(), Box<dyn Error>> {
+        let cell = self.rows.get_mut(row)
+            .and_then(|row| row.get_mut(column))
+            .ok_or("No such cell")?;
+        *cell = value;
         return Ok(());
     }
 
-    // TODO: Add condition index > 0
-    pub fn get_row_env(&self, index: usize) -> Result<HashMap<String, String>, Box<dyn Error>> {
-        let mut result: HashMap<String, String> = HashMap::new();
-        let keys = self.cells[0].clone();
-        // println!("{:?}", keys);
-        let rows = self.cells[index].clone();
+    // Variables visible to expressions evaluated on a row, index starts at 0
+    pub fn get_row_env(&self, index: usize) -> Result<HashMap<String, Value>, Box<dyn Error>> {
+        let mut result: HashMap<String, Value> = HashMap::new();
+        let row = self.rows.get(index).ok_or("No such row")?;
 
-        // let mut hax = 0;
-        for (key, cell) in zip(keys, rows) {
-            result.insert(key, cell);
-            // Special identifiers are returned here
-            result.insert(String::from("$rowcount"), index.to_string());
+        for (key, cell) in zip(&self.columns, row) {
+            result.insert(key.clone(), cell.clone());
         };
 
+        // Special identifiers are returned here
+        result.insert(String::from("$rowcount"), Value::Int(index as i64 + 1));
+
         return Ok(result);
     }
 
     // Creates a column and returns an index to the column
-    pub fn create_column(&mut self, val: &String) -> Result<usize, Box<dyn Error>> {
-        let _ = &mut self.cells[0].push(val.clone());
-        let _ = &mut self.max_width.push(val.len());
+    pub fn create_column(&mut self, val: &str) -> Result<usize, Box<dyn Error>> {
+        self.columns.push(val.to_string());
 
-        for row in &mut self.cells[1..] {
-            row.push(String::new());
+        for row in &mut self.rows {
+            row.push(Value::Null);
         };
-        
+
         return self.get_column(val);
     }
 
     // Returns the index of the column
     pub fn get_column(&self, name: &str) -> Result<usize, Box<dyn std::error::Error>> {
-        let cols = self.cells.first().ok_or("Empty table")?;
-        let index = cols.iter().position(|c| c == name).ok_or("No such column")?;
-        // Ok(self.cells.iter().map(|row| &row[index]).collect())
+        let index = self.columns.iter().position(|c| c == name).ok_or("No such column")?;
         return Ok(index);
     }
 
-    pub fn get_or_create_column(&mut self, val: &String) -> Result<usize, Box<dyn Error>> {
+    // Returns the column names, in display order
+    pub fn columns(&self) -> &[String] {
+        return &self.columns;
+    }
+
+    pub fn rename_column(&mut self, index: usize, name: &str) -> Result<(), Box<dyn Error>> {
+        let column = self.columns.get_mut(index).ok_or("No such column")?;
+        *column = name.to_string();
+        return Ok(());
+    }
+
+    // Removes a column from the header and every row
+    pub fn remove_column(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
+        if index >= self.columns.len() {
+            return Err("No such column".into());
+        }
+
+        self.columns.remove(index);
+        for row in &mut self.rows {
+            row.remove(index);
+        }
+
+        return Ok(());
+    }
+
+    // Keeps only the given columns, in the given order
+    pub fn select_columns(&mut self, indices: &[usize]) -> Result<(), Box<dyn Error>> {
+        if indices.iter().any(|&index| index >= self.columns.len()) {
+            return Err("No such column".into());
+        }
+
+        self.columns = indices.iter().map(|&index| self.columns[index].clone()).collect();
+        for row in &mut self.rows {
+            *row = indices.iter().map(|&index| row[index].clone()).collect();
+        }
+
+        return Ok(());
+    }
+
+    pub fn get_or_create_column(&mut self, val: &str) -> Result<usize, Box<dyn Error>> {
         // Little borrowing hack so rust analyzer doesnt shout at me
         if self.get_column(val).is_ok() {
             return self.get_column(val);
@@ -82,75 +122,115 @@ impl Table {
         self.create_column(val)
     }
 
-    pub fn add_col(&mut self, val: &String, fill_col: Option<String>) {
-        let row_val = fill_col.unwrap_or("<Empty>".into());
-        if self.cells.len() == 0 {
-            self.cells.push(Vec::new()); // Headers
-        }
-        self.cells[0].push(val.clone());
-        self.max_width.push(row_val.len());
-        
-        for cell in &mut self.cells[1..] {
-            cell.push(row_val.clone());
-        }
+    pub fn add_col(&mut self, val: &str, fill_col: Option<Value>) {
+        let row_val = fill_col.unwrap_or(Value::Null);
+        self.columns.push(val.to_string());
 
-        if val.len() > self.max_width[0] {
-            self.max_width[0] = val.len();
+        for row in &mut self.rows {
+            row.push(row_val.clone());
         }
     }
 
-    pub fn try_add_row(&mut self, val: Vec<String>) -> Result<(), Box<dyn Error>> {
+    pub fn try_add_row(&mut self, val: Vec<Value>) -> Result<(), Box<dyn Error>> {
         // Check the width of the columns
-        if val.len() != self.cells[0].len() {
+        if val.len() != self.columns.len() {
             return Err("Naah, wrong size blud".into());
         }
 
-        self.cells.push(val.clone());
+        self.rows.push(val);
 
-        let mut max_width = (0, 0);
-        for ev in val.iter().enumerate() {
-            if ev.1.len() > max_width.1 {
-                max_width = (ev.0, ev.1.len());
-            }
+        return Ok(());
+    }
+
+    // Returns the data rows, without the header
+    pub fn rows(&self) -> &[Vec<Value>] {
+        return &self.rows;
+    }
+
+    // Replaces the whole table, every row must be as wide as the header
+    pub fn set_data(&mut self, columns: Vec<String>, rows: Vec<Vec<Value>>) -> Result<(), Box<dyn Error>> {
+        if rows.iter().any(|row| row.len() != columns.len()) {
+            return Err("Row width does not match the number of columns".into());
+        }
+
+        self.columns = columns;
+        self.rows = rows;
+
+        return Ok(());
+    }
+
+    // Replaces every value of the column with the result of the function
+    pub fn map_column(&mut self, column: usize, f: impl Fn(&Value) -> Value) -> Result<(), Box<dyn Error>> {
+        if column >= self.columns.len() {
+            return Err("No such column".into());
         }
 
-        if self.max_width[max_width.0] > max_width.1 {
-            self.max_width[max_width.0] = max_width.1;
+        for row in &mut self.rows {
+            row[column] = f(&row[column]);
         }
 
         return Ok(());
     }
 
+    // Keeps only the data rows in start..end, dropping the rest in one go
+    pub fn keep_rows(&mut self, start: usize, end: usize) {
+        let end = end.min(self.rows.len());
+        let start = start.min(end);
+
+        self.rows.truncate(end);
+        self.rows.drain(..start);
+    }
+
     pub fn remove_row(&mut self, index: usize) -> Result<(), Box<dyn Error>> {
-        self.cells.remove(index);
+        if index >= self.rows.len() {
+            return Err("No such row".into());
+        }
+        self.rows.remove(index);
         return Ok(());
     }
 
+    // Cells without a value are shown as <Empty>
+    fn render(value: &Value) -> String {
+        if value.is_null() {
+            return EMPTY.to_string();
+        }
+        return value.to_string();
+    }
+
     pub fn display(&self) {
-        let max_width_sum: usize = self.max_width.iter().sum();
-        if self.max_width.len() == 0 {
+        if self.columns.is_empty() {
             println!("No table created, use 'generate' to create one");
             return;
         }
-        // println!("{} {:?}", max_width_sum, self.max_width);
 
-        println!("+{}+", "-".repeat(max_width_sum - 1 + self.cells[0].len() * 3));
+        let rows: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().map(Self::render).collect())
+            .collect();
+
+        let max_width: Vec<usize> = self.columns.iter().enumerate()
+            .map(|(index, column)| rows.iter()
+                .map(|row| row[index].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0))
+            .collect();
+        let max_width_sum: usize = max_width.iter().sum();
 
-        for eheader in self.cells[0].iter().enumerate() {
-            print!("| {}{} ", eheader.1, " ".repeat(self.max_width[eheader.0] - eheader.1.len()));
+        println!("+{}+", "-".repeat(max_width_sum + self.columns.len() * 3 - 1));
+
+        for eheader in self.columns.iter().enumerate() {
+            print!("| {}{} ", eheader.1, " ".repeat(max_width[eheader.0] - eheader.1.chars().count()));
         }
 
         println!("|");
-        println!("+{}+", "-".repeat(max_width_sum - 1 + self.cells[0].len() * 3));
+        println!("+{}+", "-".repeat(max_width_sum + self.columns.len() * 3 - 1));
 
-        for rows in self.cells[1..].iter() {
-            for cols in rows.iter().enumerate() {
-                print!("| {}{} ", cols.1, " ".repeat(self.max_width[cols.0] - cols.1.len()));
+        for row in rows.iter() {
+            for cols in row.iter().enumerate() {
+                print!("| {}{} ", cols.1, " ".repeat(max_width[cols.0] - cols.1.chars().count()));
             }
             println!("|");
-            println!("+{}+", "-".repeat(max_width_sum - 1 + self.cells[0].len() * 3));
+            println!("+{}+", "-".repeat(max_width_sum + self.columns.len() * 3 - 1));
         }
-
-        // return format!("{:?} {:?}", self.cells, self.max_width);
     }
-}
\ No newline at end of file
+}
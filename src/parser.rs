@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return)]
+
 use std::{ error::Error, fmt::Debug };
 
 use crate::scanner::{Token, TokenType};
@@ -29,8 +31,11 @@ pub enum ParseNodeType {
     ArithmeticOperator,
     ComparisonOperator,
     Comparison,
+    LogicalOperator,
+    Condition,
     Expression,
     Assignment,
+    Call,
     Argument,
     Command,
     Comment,
@@ -53,6 +58,7 @@ impl ParseNode {
     }
 
     /// Get first child if exists
+    #[allow(dead_code)]
     pub fn first_child(&self) -> Option<&ParseNode> {
         self.children.as_ref()?.first()
     }
@@ -73,6 +79,7 @@ impl ParseNode {
     }
 
     /// Find the first descendant node of a given type
+    #[allow(dead_code)]
     pub fn find_child_of_type(&self, kind: ParseNodeType) -> Option<&ParseNode> {
         self.children().iter().find(|c| c.variant == kind)
     }
@@ -142,13 +149,44 @@ impl Parser {
         
         return match token.token_type {
             TokenType::Number |
+            TokenType::Duration |
             TokenType::Identifier |
             TokenType::SpecialIdentifier |
-            TokenType::String => Ok(result),
+            TokenType::String |
+            TokenType::Boolean |
+            TokenType::Null => Ok(result),
             _ => Err(format!("Unexpected token {:} at line {} offset {}", token, token.line, token.offset).into())
         }
     }
 
+    // FUNCTION LPAREN RPAREN
+    // FUNCTION LPAREN OPERAND [COMMA OPERAND]... RPAREN
+    fn call(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        let token = self.expect_any()?;
+
+        if !matches!(token.token_type, TokenType::FuncKeyword(_) | TokenType::Identifier) {
+            return Err(format!("Unexpected token {:} at line {} offset {}", token, token.line, token.offset).into());
+        }
+
+        self.expect(TokenType::LParen)?;
+
+        let mut args = Vec::new();
+        if !self.is_token(TokenType::RParen) {
+            loop {
+                args.push(self.operand()?);
+
+                if !self.is_token(TokenType::Comma) {
+                    break;
+                }
+                self.expect(TokenType::Comma)?;
+            }
+        }
+
+        self.expect(TokenType::RParen)?;
+
+        return Ok(ParseNode { variant: ParseNodeType::Call, children: Some(args), token: Some(token) });
+    }
+
     // fn numeric_argument(&mut self) -> Result<ParseNode, Box<dyn Error>> {
     //     return Ok(ParseNode { variant: ParseNodeType::Argument, children: Some(vec![self.value()?]), token: None });
     // }
@@ -178,26 +216,107 @@ impl Parser {
         }
     }
 
-    // VALUE ARITHMETIC_OPERATOR VALUE
-    // VALUE ARITHMETIC_OPERATOR EXPRESSION
+    fn operator_of(&mut self, operators: &[TokenType]) -> Result<ParseNode, Box<dyn Error>> {
+        let token = self.expect_any()?;
+
+        if !operators.contains(&token.token_type) {
+            return Err(format!("Unexpected token {:} at line {} offset {}", token, token.line, token.offset).into());
+        }
+
+        return Ok(ParseNode { variant: ParseNodeType::ArithmeticOperator, children: None, token: Some(token) });
+    }
+
+    // Operators are left associative, so a - b - c is parsed as (a - b) - c
+    fn binary(&mut self, operators: &[TokenType], operand: fn(&mut Self) -> Result<ParseNode, Box<dyn Error>>) -> Result<ParseNode, Box<dyn Error>> {
+        let mut node = operand(self)?;
+
+        while operators.iter().any(|operator| self.is_token(operator.clone())) {
+            let operator = self.operator_of(operators)?;
+            let rvalue = operand(self)?;
+
+            node = ParseNode { variant: ParseNodeType::Expression, children: Some(vec![node, operator, rvalue]), token: None };
+        }
+
+        return Ok(node);
+    }
+
+    // TERM [(ADD | SUB) TERM]...
     fn expression(&mut self) -> Result<ParseNode, Box<dyn Error>> {
-        let value = self.value()?;
+        return self.binary(&[TokenType::Add, TokenType::Sub], Self::term);
+    }
+
+    // UNARY [(MUL | DIV | MOD) UNARY]...
+    fn term(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        return self.binary(&[TokenType::Mul, TokenType::Div, TokenType::Mod], Self::unary_expression);
+    }
+
+    // ARITHMETIC_OPERATOR UNARY
+    // POWER
+    fn unary_expression(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        if !self.is_token(TokenType::Add) && !self.is_token(TokenType::Sub) {
+            return self.power();
+        }
+
         let operator = self.arithmetic_operator()?;
-        
-        let pos = self.get_position();
-        let node = self.expression()
-            .or_else(|_| {
-                self.set_position(pos)?;
-                return self.value();
-            })?;
+        let node = self.unary_expression()?;
+
+        return Ok(ParseNode { variant: ParseNodeType::Expression, children: Some(vec![operator, node]), token: None });
+    }
+
+    // PRIMARY [POW UNARY]
+    // Right associative and binds tighter than a unary minus, so -2 ** 2 is -(2 ** 2)
+    fn power(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        let parenthesized = self.is_token(TokenType::LParen);
+        let base = self.primary()?;
+
+        if !self.is_token(TokenType::Pow) {
+            return Ok(base);
+        }
+
+        let operator = self.operator_of(&[TokenType::Pow])?;
+        let exponent = self.unary_expression()?;
+
+        // The scanner reads -2 as a single number, split the sign off again unless it's in parentheses as in (-2) ** 2
+        if let Some(token) = base.token.as_ref().filter(|token| !parenthesized && token.token_type == TokenType::Number) {
+            if let Some(digits) = token.value.as_ref().and_then(|value| value.strip_prefix('-')) {
+                let sign = Token { token_type: TokenType::Sub, value: None, ..token.clone() };
+                let base = ParseNode { variant: ParseNodeType::Value, children: None, token: Some(Token { value: Some(digits.to_string()), ..token.clone() }) };
+                let power = ParseNode { variant: ParseNodeType::Expression, children: Some(vec![base, operator, exponent]), token: None };
+                let sign = ParseNode { variant: ParseNodeType::ArithmeticOperator, children: None, token: Some(sign) };
 
-        return Ok(ParseNode { variant: ParseNodeType::Expression, children: Some(vec![value, operator, node]), token: None });
+                return Ok(ParseNode { variant: ParseNodeType::Expression, children: Some(vec![sign, power]), token: None });
+            }
+        }
+
+        return Ok(ParseNode { variant: ParseNodeType::Expression, children: Some(vec![base, operator, exponent]), token: None });
     }
 
-    // IDENTIFIER EQUAL VALUE
-    // IDENTIFIER EQUAL EXPRESSION
-    // SPECIAL_IDENTIFIER EQUAL VALUE
-    // SPECIAL_IDENTIFIER EQUAL EXPRESSION
+    // LPAREN OPERAND RPAREN
+    // CALL
+    // VALUE
+    fn primary(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        if self.is_token(TokenType::LParen) {
+            self.expect(TokenType::LParen)?;
+            let node = self.operand()?;
+            self.expect(TokenType::RParen)?;
+            return Ok(node);
+        }
+
+        if self.is_call() {
+            return self.call();
+        }
+
+        return self.value();
+    }
+
+    // A function name directly followed by a parenthesis
+    fn is_call(&self) -> bool {
+        let is_name = self.peek().is_some_and(|token| matches!(token.token_type, TokenType::FuncKeyword(_) | TokenType::Identifier));
+        return is_name && self.tokens.get(self.current_index + 1).is_some_and(|token| token.token_type == TokenType::LParen);
+    }
+
+    // IDENTIFIER EQUAL OPERAND
+    // SPECIAL_IDENTIFIER EQUAL OPERAND
     fn assignment(&mut self) -> Result<ParseNode, Box<dyn Error>> {
         let mut children = Vec::new();
         
@@ -214,14 +333,7 @@ impl Parser {
         children.push(identifier); // Identifier required
         self.expect(TokenType::Eq)?;
         
-        let pos = self.get_position();
-        let node = self.expression()
-            .or_else(|_| {
-                self.set_position(pos)?;
-                return self.value();
-            })?;
-
-        children.push(node);
+        children.push(self.operand()?);
 
         return Ok(ParseNode { variant: ParseNodeType::Assignment, children: Some(children), token: None });
     }
@@ -235,6 +347,8 @@ impl Parser {
         return match token.token_type {
             TokenType::Eeq |
             TokenType::Neq |
+            TokenType::Match |
+            TokenType::NotMatch |
             TokenType::Lt  |
             TokenType::Lte | 
             TokenType::Gt  |
@@ -243,19 +357,98 @@ impl Parser {
         }
     }
 
-    // VALUE COMPARISON_OPERATOR VALUE
+    // EXPRESSION [COMPARISON_OPERATOR EXPRESSION]
+    // EXPRESSION IN LPAREN EXPRESSION [COMMA EXPRESSION]... RPAREN
     fn comparison(&mut self) -> Result<ParseNode, Box<dyn Error>> {
-        let mut children = Vec::new();
+        let mut children = vec![self.expression()?];
+
+        if self.is_token(TokenType::In) {
+            // The values to look for follow the operator, e.g. [x, in, "a", "b"]
+            let token = self.expect(TokenType::In)?;
+            children.push(ParseNode { variant: ParseNodeType::ComparisonOperator, children: None, token: Some(token) });
 
-        children.push(self.value()?);
-        children.push(self.comparison_operator()?);
-        children.push(self.value()?);
+            self.expect(TokenType::LParen)?;
+            loop {
+                children.push(self.expression()?);
+
+                if !self.is_token(TokenType::Comma) {
+                    break;
+                }
+                self.expect(TokenType::Comma)?;
+            }
+            self.expect(TokenType::RParen)?;
+        } else if self.is_comparison_operator() {
+            children.push(self.comparison_operator()?);
+            children.push(self.expression()?);
+        } else {
+            // A lone expression, e.g. a bool field or a function returning one
+            return Ok(children.remove(0));
+        }
 
         return Ok(ParseNode { variant: ParseNodeType::Comparison, children: Some(children), token: None });
     }
 
+    fn is_comparison_operator(&self) -> bool {
+        return self.peek().is_some_and(|token| matches!(
+            token.token_type,
+            TokenType::Eeq | TokenType::Neq | TokenType::Match | TokenType::NotMatch | TokenType::Lt | TokenType::Lte | TokenType::Gt | TokenType::Gte
+        ));
+    }
+
+    fn logical_operator(&mut self, operator: TokenType) -> Result<ParseNode, Box<dyn Error>> {
+        let token = self.expect(operator)?;
+        return Ok(ParseNode { variant: ParseNodeType::LogicalOperator, children: None, token: Some(token) });
+    }
+
+    // CONJUNCTION [OR CONJUNCTION]...
+    fn condition(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        let mut node = self.conjunction()?;
+
+        while self.is_token(TokenType::Or) {
+            let operator = self.logical_operator(TokenType::Or)?;
+            let rvalue = self.conjunction()?;
+
+            node = ParseNode { variant: ParseNodeType::Condition, children: Some(vec![node, operator, rvalue]), token: None };
+        }
+
+        return Ok(node);
+    }
+
+    // NEGATION [AND NEGATION]...
+    fn conjunction(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        let mut node = self.negation()?;
+
+        while self.is_token(TokenType::And) {
+            let operator = self.logical_operator(TokenType::And)?;
+            let rvalue = self.negation()?;
+
+            node = ParseNode { variant: ParseNodeType::Condition, children: Some(vec![node, operator, rvalue]), token: None };
+        }
+
+        return Ok(node);
+    }
+
+    // NOT NEGATION
+    // COMPARISON
+    fn negation(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        if self.is_token(TokenType::Not) {
+            let operator = self.logical_operator(TokenType::Not)?;
+            let node = self.negation()?;
+
+            return Ok(ParseNode { variant: ParseNodeType::Condition, children: Some(vec![operator, node]), token: None });
+        }
+
+        return self.comparison();
+    }
+
+    // Conditions and expressions share one grammar, from loosest to tightest binding:
+    // or, and, not, comparisons, + and -, * / and %, unary signs, ** and finally values, calls and parentheses.
+    // Every token is read once, so x > 3 is a condition and (a + b) * 2 an expression.
+    fn operand(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        return self.condition();
+    }
+
     fn argument(&mut self) -> Result<ParseNode, Box<dyn Error>> {
-        let mut pos = self.get_position();
         let mut nodes = Vec::new();
 
         loop {
@@ -263,23 +456,40 @@ impl Parser {
                 break;
             }
 
-            let node = self.assignment()
-                .or_else(|_| {
-                    self.set_position(pos)?;
-                    return self.comparison();
-                })
-                .or_else(|_| {
-                    self.set_position(pos)?;
-                    return self.value();
-                })?;
+            let is_assignment = self.peek().is_some_and(|token| matches!(token.token_type, TokenType::Identifier | TokenType::SpecialIdentifier))
+                && self.tokens.get(self.current_index + 1).is_some_and(|token| token.token_type == TokenType::Eq);
 
+            let node = if is_assignment { self.assignment()? } else { self.argument_item()? };
             nodes.push(node);
-            pos = self.get_position();
         };
 
         return Ok(ParseNode { variant: ParseNodeType::Argument, children: Some(nodes), token: None })
     }
 
+    // CONDITION, CALL or VALUE
+    // Arithmetic on its own isn't an argument, so in field lists a - b stays the field a, the flag - and the field b
+    fn argument_item(&mut self) -> Result<ParseNode, Box<dyn Error>> {
+        let pos = self.get_position();
+
+        let result = self.operand();
+        if result.as_ref().is_ok_and(|node| node.variant != ParseNodeType::Expression) {
+            return result;
+        }
+
+        self.set_position(pos)?;
+        if self.is_call() {
+            return self.call();
+        }
+
+        return self.value()
+            .or_else(|_| {
+                // Lone operators act as flags, e.g. | remove -keep a, b
+                self.set_position(pos)?;
+                return self.arithmetic_operator();
+            })
+            .map_err(|err| result.err().unwrap_or(err));
+    }
+
     fn arguments(&mut self) -> Result<Vec<ParseNode>, Box<dyn Error>> {
         let mut args = Vec::new();
         
@@ -290,6 +500,11 @@ impl Parser {
             }
 
             args.push(self.argument()?);
+
+            // Arguments are separated by commas
+            if self.is_token(TokenType::Comma) {
+                self.expect(TokenType::Comma)?;
+            }
         }
 
         return Ok(args);
@@ -381,8 +596,8 @@ impl Parser {
         }
     }
 
-    pub fn parse(&mut self, tokens: &Vec<Token>) -> Result<(), Box<dyn Error>> {
-        self.tokens = tokens.clone();
+    pub fn parse(&mut self, tokens: &[Token]) -> Result<(), Box<dyn Error>> {
+        self.tokens = tokens.to_vec();
         'state: loop {
             if self.peek().is_none() {
                 return Err("Unexpected EOF".into());
@@ -403,7 +618,7 @@ impl Parser {
     }
 }
 
-pub fn parse(tokens: &Vec<Token>) -> Result<ParseNode, Box<dyn Error>> {
+pub fn parse(tokens: &[Token]) -> Result<ParseNode, Box<dyn Error>> {
     // let mut output: Table = Table::new();
     let mut ctx = Parser::new();
     ctx.parse(tokens)?;
@@ -412,3 +627,62 @@ pub fn parse(tokens: &Vec<Token>) -> Result<ParseNode, Box<dyn Error>> {
     
     return Ok(ctx.parse_tree.clone()); // Yuck
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::scan;
+
+    // The nodes of the first argument of the first command
+    fn argument(query: &str) -> Vec<ParseNode> {
+        let tree = parse(&scan(query)).unwrap();
+        let command = tree.child(0).and_then(|statement| statement.child(0)).unwrap();
+        return command.child(0).unwrap().children().to_vec();
+    }
+
+    fn shape(node: &ParseNode) -> String {
+        if node.children().is_empty() {
+            return match &node.token {
+                Some(token) => token.value.clone().unwrap_or(format!("{:?}", token.token_type)),
+                None => String::new()
+            };
+        }
+        let children: Vec<String> = node.children().iter().map(shape).collect();
+        return match node.variant {
+            ParseNodeType::Call => format!("{}({})", node.token_value().unwrap(), children.join(", ")),
+            _ => format!("({})", children.join(" "))
+        };
+    }
+
+    #[test]
+    fn follows_operator_precedence() {
+        let nodes = argument("| eval x = 1 + 2 * 3 ** 2 > 4 and not a or b");
+        assert_eq!(shape(&nodes[0]), "(x ((((1 Add (2 Mul (3 Pow 2))) Gt 4) And (Not a)) Or b))");
+
+        let nodes = argument("| eval x = -2 ** 2 - (a - b) - c");
+        assert_eq!(shape(&nodes[0]), "(x (((Sub (2 Pow 2)) Sub (a Sub b)) Sub c))");
+    }
+
+    #[test]
+    fn parses_in_lists_and_calls() {
+        let nodes = argument("| where status in (200, 2 * 102) and contains(host, \"web\")");
+        assert_eq!(shape(&nodes[0]), "((status In 200 (2 Mul 102)) And contains(host, web))");
+    }
+
+    #[test]
+    fn parses_nested_calls_in_linear_time() {
+        // Trying every rule on every operand took seconds at a depth of 12
+        let call = (0..30).fold(String::from("x"), |inner, _| format!("abs({})", inner));
+        let nodes = argument(&format!("| eval y = {}", call));
+        assert_eq!(nodes.len(), 1);
+    }
+
+    #[test]
+    fn keeps_flags_in_field_lists() {
+        let nodes = argument("| fields - a");
+        assert_eq!(nodes.iter().map(shape).collect::<Vec<String>>(), ["Sub", "a"]);
+
+        let nodes = argument("| sort -n");
+        assert_eq!(nodes.iter().map(shape).collect::<Vec<String>>(), ["Sub", "n"]);
+    }
+}
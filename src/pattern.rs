@@ -0,0 +1,46 @@
+// Regular expressions, compiled once per query and shared by every row.
+// The syntax is the one of the regex crate, named groups are written (?<name>...) or (?P<name>...).
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::RefCell, collections::HashMap, error::Error, rc::Rc};
+
+use regex::Regex;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Rc<Regex>>> = RefCell::new(HashMap::new());
+}
+
+// Returns the compiled pattern, compiling it on first use
+pub fn compile(pattern: &str) -> Result<Rc<Regex>, Box<dyn Error>> {
+    if let Some(regex) = CACHE.with(|cache| cache.borrow().get(pattern).cloned()) {
+        return Ok(regex);
+    }
+
+    let regex = Rc::new(Regex::new(pattern)
+        .map_err(|err| format!("Invalid regular expression \"{}\": {}", pattern, err))?);
+
+    CACHE.with(|cache| cache.borrow_mut().insert(pattern.to_string(), regex.clone()));
+
+    return Ok(regex);
+}
+
+// Forgets the compiled patterns, called before every query
+pub fn clear() {
+    CACHE.with(|cache| cache.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiles_every_pattern_once_per_query() {
+        let first = compile(r"\d+").unwrap();
+        assert!(Rc::ptr_eq(&first, &compile(r"\d+").unwrap()));
+
+        clear();
+        assert!(!Rc::ptr_eq(&first, &compile(r"\d+").unwrap()));
+        assert!(compile("(").unwrap_err().to_string().starts_with("Invalid regular expression \"(\""));
+    }
+}
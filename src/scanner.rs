@@ -1,4 +1,6 @@
-use std::{collections::HashMap, error::Error, fmt::Display, hash::Hash, iter::{self, Enumerate, Map, Peekable}, str::Chars};
+#![allow(clippy::needless_return)]
+
+use std::{error::Error, fmt::Display};
 
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,7 +12,23 @@ pub enum CommandKeyword {
     Rename,
     Top,
     Stats,
-    Remove
+    Remove,
+    Sort,
+    Head,
+    Tail,
+    Dedup,
+    Fields,
+    Rex,
+    Bin,
+    Makemv,
+    Mvexpand,
+    Nomv,
+    Inputcsv,
+    Outputcsv,
+    Outputjson,
+    Inputjson,
+    Spath,
+    Kv
 }
 
 impl TryFrom<&String> for CommandKeyword {
@@ -26,12 +44,45 @@ impl TryFrom<&String> for CommandKeyword {
             "top" => Ok(Self::Top),
             "stats" => Ok(Self::Stats),
             "remove" => Ok(Self::Remove),
+            "sort" => Ok(Self::Sort),
+            "head" => Ok(Self::Head),
+            "tail" => Ok(Self::Tail),
+            "dedup" => Ok(Self::Dedup),
+            "fields" => Ok(Self::Fields),
+            "rex" => Ok(Self::Rex),
+            "bin" => Ok(Self::Bin),
+            "makemv" => Ok(Self::Makemv),
+            "mvexpand" => Ok(Self::Mvexpand),
+            "nomv" => Ok(Self::Nomv),
+            "inputcsv" => Ok(Self::Inputcsv),
+            "outputcsv" => Ok(Self::Outputcsv),
+            "outputjson" => Ok(Self::Outputjson),
+            "inputjson" => Ok(Self::Inputjson),
+            "spath" => Ok(Self::Spath),
+            "kv" => Ok(Self::Kv),
             // Add more commands here
             _ => Err("Unknown".into())
         }
     }
 }
 
+impl CommandKeyword {
+    // Commands taking expressions, where '*' multiplies and and/or/not are operators
+    pub fn accepts_expressions(&self) -> bool {
+        return matches!(self, Self::Eval | Self::Where | Self::Head | Self::Tail);
+    }
+
+    // Commands taking field lists, where '*' is a wildcard (e.g. | rename src_* as *)
+    pub fn accepts_wildcards(&self) -> bool {
+        return !self.accepts_expressions();
+    }
+
+    // Commands where 5m is a duration, elsewhere it is the number 5 followed by the name m
+    pub fn accepts_durations(&self) -> bool {
+        return self.accepts_expressions() || matches!(self, Self::Bin);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FuncKeyword {
     Len,
@@ -74,12 +125,19 @@ impl TryFrom<&String> for FuncKeyword {
 // }
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum TokenType {
     Add,
     Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
     Eq,
     Eeq,
     Neq,
+    Match,
+    NotMatch,
     Gt,
     Lt,
     Gte,
@@ -90,10 +148,15 @@ pub enum TokenType {
     RParen,
     And,
     Or,
+    Not,
+    In,
+    Boolean,
+    Null,
     CommandKeyword(CommandKeyword),
     FuncKeyword(FuncKeyword),
     Identifier,
     Number,
+    Duration,
     String,
     Comment,
     SpecialIdentifier,
@@ -121,6 +184,9 @@ struct Scanner {
     data: Vec<u8>,
     tokens: Vec<Token>,
     
+    // The command whose arguments are currently being scanned
+    _command: Option<CommandKeyword>,
+
     _current_index: usize,
     _data_size: usize,
     
@@ -134,6 +200,7 @@ impl Scanner {
         return Scanner {
             data: Vec::new(), 
             tokens: Vec::new(),
+            _command: None,
             _current_index: 0, 
             _data_size: 0,
             _line: 1,
@@ -151,6 +218,10 @@ impl Scanner {
         self.data.get(self._current_index).copied()
     }
 
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.data.get(self._current_index + offset).copied()
+    }
+
     fn advance(&mut self) -> Option<u8> {
         let res = self.peek();
         self._current_index += 1;
@@ -174,6 +245,9 @@ impl Scanner {
             if c == b'=' {
                 self.add_token(TokenType::Eeq, None);
                 self.advance();
+            } else if c == b'~' {
+                self.add_token(TokenType::Match, None);
+                self.advance();
             } else {
                 self.add_token(TokenType::Eq, None);
             }
@@ -194,7 +268,7 @@ impl Scanner {
         self._line += 1;
         self._line_index = 0;
 
-        if should_advance == true {
+        if should_advance {
             self.advance();
         }
     }
@@ -202,43 +276,60 @@ impl Scanner {
     fn handle_comment(&mut self) {
         self.advance(); // We know this is "*"
         let mut val: String = String::new();
-        loop {
-            if let Some(c) = self.advance() {
-                if c == b'*' && self.advance().unwrap_or(0) == b'/' {
-                    val = val.trim().to_owned();
-                    self.add_token(TokenType::Comment, Some(val));
-                    break;
-                } 
-                else if c == b'\n' {
-                    self.handle_newline(false);
-                } 
-                else {
-                    val.push(c as char);
-                }
-            } else {
+        while let Some(c) = self.advance() {
+            if c == b'*' && self.advance().unwrap_or(0) == b'/' {
+                val = val.trim().to_owned();
+                self.add_token(TokenType::Comment, Some(val));
                 break;
+            } 
+            else if c == b'\n' {
+                self.handle_newline(false);
+            } 
+            else {
+                val.push(c as char);
             }
         }
     }
 
     fn handle_slash(&mut self) {
         self.advance();
-        if let Some(c) = self.peek() {
-            match c {
-                b'*' => self.handle_comment(),
-                _ => {}
-            }
-        };
+        if self.peek() == Some(b'*') {
+            self.handle_comment();
+        } else {
+            self.add_token(TokenType::Div, None);
+        }
+    }
+
+    fn handle_star(&mut self) {
+        // In field lists '*' is a wildcard, e.g. | rename src_* as *
+        if self.is_wildcard(b'*') {
+            self.handle_identifier();
+            return;
+        }
+
+        self.advance(); // We know this is '*'
+
+        if self.peek() == Some(b'*') {
+            self.add_token(TokenType::Pow, None);
+            self.advance();
+        } else {
+            self.add_token(TokenType::Mul, None);
+        }
+    }
+
+    fn handle_percent(&mut self) {
+        self.add_token(TokenType::Mod, None);
+        self.advance();
     }
 
     fn is_alpha(&mut self, val: u8) -> bool {
         // uppercase letters
-        if val >= 65 && val <= 90 {
+        if (65..=90).contains(&val) {
             return true
         }
 
         // lowercase letters
-        if val >= 97 && val <= 122 {
+        if (97..=122).contains(&val) {
             return true
         }
 
@@ -246,64 +337,144 @@ impl Scanner {
     }
 
     fn is_numeric(&mut self, val: u8) -> bool {
-        if val >= 48 && val <= 57 {
+        if (48..=57).contains(&val) {
             return true
         }
 
         return false
     }
 
+    fn is_wildcard(&mut self, val: u8) -> bool {
+        return val == b'*' && self._command.as_ref().is_some_and(|command| command.accepts_wildcards());
+    }
+
+    fn is_identifier(&mut self, val: u8) -> bool {
+        return self.is_alpha(val) || self.is_numeric(val) || val == b'_' || self.is_wildcard(val);
+    }
+
     fn handle_identifier(&mut self) {
         let mut val: String = String::new();
-        loop {
-            // println!("AA");
-            if let Some(c) = self.peek() {
-                if self.is_alpha(c) || self.is_numeric(c) {
-                    val.push(c as char);
-                    self.advance();
-                } else {
-                    // If the keyword returns an error, just assume it is an identifier
-                    if let Ok(command) = CommandKeyword::try_from(&val) {
-                        self.add_token(TokenType::CommandKeyword(command), None);
-                    } else if let Ok(func) = FuncKeyword::try_from(&val) {
-                        if c == b'(' {
-                            self.add_token(TokenType::FuncKeyword(func), None);
-                        } else {
-                            self.add_token(TokenType::Identifier, Some(val));
-                        }
-                    } else {
-                        self.add_token(TokenType::Identifier, Some(val));
-                    }
-                    break;
-                }
+
+        while let Some(c) = self.peek() {
+            // A dot joins the parts of a flattened name such as user.id
+            let is_dot = c == b'.' && self.peek_at(1).is_some_and(|next| self.is_identifier(next));
+            if !self.is_identifier(c) && !is_dot {
+                break;
+            }
+            val.push(c as char);
+            self.advance();
+        }
+
+        let accepts_expressions = self._command.as_ref().is_some_and(|command| command.accepts_expressions());
+
+        // Command names are only commands at the start of a pipeline or after a '|', elsewhere they are field names (e.g. | stats count by bin)
+        let starts_command = self.tokens.iter().rev()
+            .find(|token| token.token_type != TokenType::Comment)
+            .is_none_or(|token| token.token_type == TokenType::Separator);
+
+        // If the keyword returns an error, just assume it is an identifier
+        if let Some(operator) = Self::keyword_operator(&val).filter(|_| accepts_expressions) {
+            self.add_token(operator, None);
+        } else if let Some(literal) = Self::literal(&val).filter(|_| accepts_expressions) {
+            self.add_token(literal, Some(val));
+        } else if let Some(command) = CommandKeyword::try_from(&val).ok().filter(|_| starts_command) {
+            self._command = Some(command.clone());
+            self.add_token(TokenType::CommandKeyword(command), None);
+        } else if let Ok(func) = FuncKeyword::try_from(&val) {
+            if self.peek() == Some(b'(') {
+                self.add_token(TokenType::FuncKeyword(func), Some(val));
             } else {
                 self.add_token(TokenType::Identifier, Some(val));
-                break;
             }
+        } else {
+            self.add_token(TokenType::Identifier, Some(val));
         }
     }
 
+    fn keyword_operator(val: &str) -> Option<TokenType> {
+        return match val {
+            "and" => Some(TokenType::And),
+            "or" => Some(TokenType::Or),
+            "not" => Some(TokenType::Not),
+            "in" => Some(TokenType::In),
+            _ => None
+        };
+    }
+
+    fn literal(val: &str) -> Option<TokenType> {
+        return match val {
+            "true" | "false" => Some(TokenType::Boolean),
+            "null" => Some(TokenType::Null),
+            _ => None
+        };
+    }
+
+    // Digits, an optional fraction and an optional exponent, e.g. 42, 0.25, 1.5e-3
     fn handle_number(&mut self) {
         let mut val: String = String::new();
-        
-        loop {
-            if let Some(c) = self.peek() {
-                if self.is_numeric(c) == true {
-                    val.push(c as char);
+
+        // A leading '-' is only here when it was scanned as a unary minus
+        if self.peek() == Some(b'-') {
+            val.push('-');
+            self.advance();
+        }
+
+        self.take_digits(&mut val);
+
+        if self.peek() == Some(b'.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            val.push('.');
+            self.advance();
+            self.take_digits(&mut val);
+        }
+
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let exponent_digit = match self.peek_at(1) {
+                Some(b'+' | b'-') => 2,
+                _ => 1
+            };
+
+            if self.peek_at(exponent_digit).is_some_and(|c| c.is_ascii_digit()) {
+                for _ in 0..exponent_digit {
+                    val.push(self.advance().unwrap() as char);
+                }
+                self.take_digits(&mut val);
+            }
+        }
+
+        // Durations are a number directly followed by a unit, e.g. 5m or 250ms
+        let accepts_durations = self._command.as_ref().is_some_and(|command| command.accepts_durations());
+        if accepts_durations && !val.contains(['e', 'E']) {
+            let unit = ["ms", "s", "m", "h", "d", "w"].into_iter().find(|unit| {
+                let length = unit.len();
+                return unit.bytes().enumerate().all(|(index, c)| self.peek_at(index) == Some(c))
+                    && !self.peek_at(length).is_some_and(|c| self.is_identifier(c));
+            });
+
+            if let Some(unit) = unit {
+                for _ in 0..unit.len() {
                     self.advance();
-                } else {
-                    self.add_token(TokenType::Number, Some(val));
-                    break;
                 }
-            } else {
-                self.add_token(TokenType::Number, Some(val));
+                self.add_token(TokenType::Duration, Some(val + unit));
+                return;
+            }
+        }
+
+        self.add_token(TokenType::Number, Some(val));
+    }
+
+    fn take_digits(&mut self, val: &mut String) {
+        while let Some(c) = self.peek() {
+            if !self.is_numeric(c) {
                 break;
             }
+            val.push(c as char);
+            self.advance();
         }
     }
 
     fn handle_string(&mut self) {
-        let mut val: String = String::new();
+        // Collect bytes, so characters outside ASCII come out whole
+        let mut val: Vec<u8> = Vec::new();
 
         // We already know this is the "\"" character, so we advance once
         self.advance();
@@ -311,14 +482,19 @@ impl Scanner {
         loop {
             if let Some(c) = self.advance() {
                 if c == b'\"' {
-                    self.add_token(TokenType::String, Some(val));
+                    self.add_token(TokenType::String, Some(String::from_utf8_lossy(&val).into_owned()));
                     break;
                 } 
                 else if c == b'\n' {
                     self.handle_newline(false);
                 }
+                // \\, \", \t and \n are escapes, any other backslash is kept as it is (e.g. for \d in patterns)
+                else if let Some(escaped) = self.peek().filter(|_| c == b'\\').and_then(Self::escaped) {
+                    self.advance();
+                    val.push(escaped);
+                }
                 else {
-                    val.push(c as char);
+                    val.push(c);
                 }
                 continue;
             }
@@ -327,11 +503,22 @@ impl Scanner {
         }
     }
 
+    // The character an escape in a string stands for, from the character after the backslash
+    fn escaped(c: u8) -> Option<u8> {
+        return match c {
+            b'\\' => Some(b'\\'),
+            b'\"' => Some(b'\"'),
+            b't' => Some(b'\t'),
+            b'n' => Some(b'\n'),
+            _ => None
+        };
+    }
+
     fn handle_rest(&mut self) {
         if let Some(c) = self.peek() {
-            if self.is_numeric(c) == true {
+            if self.is_numeric(c) {
                 self.handle_number();
-            } else if self.is_alpha(c) == true {
+            } else if self.is_alpha(c) || c == b'_' {
                 self.handle_identifier();
             } else {
                 self.advance();
@@ -366,6 +553,18 @@ impl Scanner {
     }
 
     fn handle_sub(&mut self) {
+        // A minus directly before a number is part of the number, unless it follows a value (e.g. x -3)
+        let follows_value = self.tokens.last().is_some_and(|token| matches!(
+            token.token_type,
+            TokenType::Number | TokenType::Duration | TokenType::Boolean | TokenType::Null
+                | TokenType::Identifier | TokenType::SpecialIdentifier | TokenType::String | TokenType::RParen
+        ));
+
+        if !follows_value && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.handle_number();
+            return;
+        }
+
         self.add_token(TokenType::Sub, None);
         self.advance();
     }
@@ -408,11 +607,14 @@ impl Scanner {
 
     fn handle_exclamation(&mut self) {
         self.advance();
-        if let Some(c) = self.peek() {
-            if c == b'=' {
-                self.add_token(TokenType::Neq, None);
-                self.advance();
-            } // else if !self.is_alphanumeric() { TokenType::Not }
+        if self.peek() == Some(b'=') {
+            self.add_token(TokenType::Neq, None);
+            self.advance();
+        } else if self.peek() == Some(b'~') {
+            self.add_token(TokenType::NotMatch, None);
+            self.advance();
+        } else {
+            self.add_token(TokenType::Not, None);
         }
     }
 
@@ -434,7 +636,9 @@ impl Scanner {
                     b'='  => self.handle_equals(),
                     b','  => self.handle_comma(),
                     b'|'  => self.handle_seperator(),
+                    b'*'  => self.handle_star(),
                     b'/'  => self.handle_slash(),
+                    b'%'  => self.handle_percent(),
                     b'\n' => self.handle_newline(true),
                     b'\"' => self.handle_string(),
                     b'$'  => self.handle_special_identifier(),
@@ -457,4 +661,63 @@ impl Scanner {
 pub(crate) fn scan(text: &str) -> Vec<Token> {
     let mut ctx = Scanner::new();
     return ctx.scan(text);
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(text: &str) -> Vec<TokenType> {
+        return scan(text).into_iter().map(|token| token.token_type).collect();
+    }
+
+    #[test]
+    fn reads_command_names_as_commands_only_after_a_separator() {
+        assert_eq!(types("| stats count by bin | bin kv"), [
+            TokenType::Separator, TokenType::CommandKeyword(CommandKeyword::Stats),
+            TokenType::Identifier, TokenType::Identifier, TokenType::Identifier,
+            TokenType::Separator, TokenType::CommandKeyword(CommandKeyword::Bin),
+            TokenType::Identifier, TokenType::EOF
+        ]);
+        assert_eq!(types("/* first */ | top"), [TokenType::Comment, TokenType::Separator, TokenType::CommandKeyword(CommandKeyword::Top), TokenType::EOF]);
+    }
+
+    #[test]
+    fn reads_durations_only_where_they_are_expected() {
+        assert_eq!(types("| eval a = 5m")[4], TokenType::Duration);
+        assert_eq!(types("| bin t span=5m")[5], TokenType::Duration);
+        assert_eq!(types("| head 5m"), [
+            TokenType::Separator, TokenType::CommandKeyword(CommandKeyword::Head), TokenType::Duration, TokenType::EOF
+        ]);
+        assert_eq!(types("| top 5m"), [
+            TokenType::Separator, TokenType::CommandKeyword(CommandKeyword::Top), TokenType::Number, TokenType::Identifier, TokenType::EOF
+        ]);
+    }
+
+    #[test]
+    fn reads_a_minus_after_a_value_as_subtraction() {
+        assert_eq!(types("| eval a = 5m -3")[4..], [TokenType::Duration, TokenType::Sub, TokenType::Number, TokenType::EOF]);
+        assert_eq!(types("| eval a = true -1")[4..], [TokenType::Boolean, TokenType::Sub, TokenType::Number, TokenType::EOF]);
+        assert_eq!(types("| eval a = null -1")[4..], [TokenType::Null, TokenType::Sub, TokenType::Number, TokenType::EOF]);
+        assert_eq!(types("| eval a = -1")[4..], [TokenType::Number, TokenType::EOF]);
+    }
+
+    #[test]
+    fn reads_floats_and_negative_numbers() {
+        let numbers: Vec<String> = scan("| eval a = -1.5 + 2.25e-3 - -3 * 1E2 + x-1")
+            .into_iter()
+            .filter(|token| token.token_type == TokenType::Number)
+            .map(|token| token.value.unwrap())
+            .collect();
+        assert_eq!(numbers, ["-1.5", "2.25e-3", "-3", "1E2", "1"]);
+    }
+
+    #[test]
+    fn reads_escapes_in_strings() {
+        let strings: Vec<String> = scan(r#"| eval a = "say \"hi\"" + "C:\\" + "a\tb\nc" + "\d+\." + "\\d""#)
+            .into_iter()
+            .filter(|token| token.token_type == TokenType::String)
+            .map(|token| token.value.unwrap())
+            .collect();
+        assert_eq!(strings, ["say \"hi\"", "C:\\", "a\tb\nc", "\\d+\\.", "\\d"]);
+    }
+}
@@ -0,0 +1,561 @@
+// Timestamps are milliseconds since the Unix epoch, always in UTC.
+// Durations are numbers of seconds, like in tostring(x, "duration"), so now() - 5m is five minutes ago.
+//
+// The clock is read once per query, so every row sees the same now(). Set the SCRAP_NOW environment
+// variable to epoch seconds or an ISO 8601 timestamp (e.g. 2024-03-01T12:00:00Z) for repeatable results.
+
+#![allow(clippy::needless_return)]
+
+use std::{cell::Cell, error::Error, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::value::Value;
+
+const SECOND: i64 = 1000;
+const MINUTE: i64 = 60 * SECOND;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+
+// The format timestamps are displayed with, e.g. 2024-03-01T12:30:00.000Z
+pub const ISO_8601: &str = "%Y-%m-%dT%H:%M:%S.%3NZ";
+
+const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"];
+const WEEKDAYS: [&str; 7] = ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"];
+
+thread_local! {
+    static NOW: Cell<Option<i64>> = const { Cell::new(None) };
+}
+
+// The current time, fixed for the rest of the query on first use
+pub fn now() -> i64 {
+    if let Some(now) = NOW.with(Cell::get) {
+        return now;
+    }
+
+    let now = std::env::var("SCRAP_NOW").ok()
+        .and_then(|now| parse_timestamp(now.trim()))
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_millis() as i64).unwrap_or(0));
+
+    NOW.with(|cell| cell.set(Some(now)));
+    return now;
+}
+
+// Lets the clock move on, called before every query
+pub fn reset_clock() {
+    NOW.with(|cell| cell.set(None));
+}
+
+// Epoch seconds or an ISO 8601 timestamp, e.g. 2024-03-01T12:00:00Z or 2024-03-01
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    if let Ok(seconds) = text.parse::<f64>() {
+        return from_seconds(seconds);
+    }
+    return ["%Y-%m-%dT%H:%M:%S.%3N%z", "%Y-%m-%dT%H:%M:%S%z", "%Y-%m-%d"].iter()
+        .find_map(|format| strptime(text, format));
+}
+
+// Epoch seconds to a timestamp, None when it doesn't fit
+pub fn from_seconds(seconds: f64) -> Option<i64> {
+    let millis = (seconds * 1000.0).round();
+    if millis.is_finite() && millis.abs() < i64::MAX as f64 {
+        return Some(millis as i64);
+    }
+    return None;
+}
+
+// A number of milliseconds as seconds, an int when it is a whole number of seconds
+pub fn to_seconds(millis: i64) -> Value {
+    return Value::Int(millis).div(&Value::Int(SECOND)).unwrap_or(Value::Null);
+}
+
+// Duration literals, e.g. 250ms, 30s, 5m, 2h, 1d or 1w, as a number of seconds
+pub fn parse_duration(text: &str) -> Option<Value> {
+    let split = text.find(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E')?;
+    let (number, unit) = text.split_at(split);
+
+    let unit = match unit {
+        "ms" => 1,
+        "s" => SECOND,
+        "m" => MINUTE,
+        "h" => HOUR,
+        "d" => DAY,
+        "w" => WEEK,
+        _ => return None
+    };
+
+    let millis = Value::parse_number(number)?.mul(&Value::Int(unit)).ok()?;
+    return millis.div(&Value::Int(SECOND)).ok();
+}
+
+// Days since the epoch of a date in the proleptic Gregorian calendar (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    return era * 146_097 + day_of_era - 719_468;
+}
+
+// Year, month and day of a number of days since the epoch
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    return (year, month, day);
+}
+
+fn is_leap_year(year: i64) -> bool {
+    return year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    return match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    };
+}
+
+// The fields of a timestamp
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    millis: i64
+}
+
+impl DateTime {
+    fn new(timestamp: i64) -> DateTime {
+        let (year, month, day) = civil_from_days(timestamp.div_euclid(DAY));
+        let time = timestamp.rem_euclid(DAY);
+
+        return DateTime {
+            year, month, day,
+            hour: time / HOUR,
+            minute: time / MINUTE % 60,
+            second: time / SECOND % 60,
+            millis: time % SECOND
+        };
+    }
+
+    fn timestamp(&self) -> Option<i64> {
+        let days = days_from_civil(self.year, self.month, self.day);
+        return days.checked_mul(DAY)?
+            .checked_add(self.hour * HOUR + self.minute * MINUTE + self.second * SECOND + self.millis);
+    }
+}
+
+// Formats a timestamp, with these conversions:
+// %Y year, %y two digit year, %m month, %d day, %e space padded day, %j day of the year,
+// %H hour (00-23), %I hour (01-12), %p AM or PM, %M minute, %S second, %3N or %L milliseconds,
+// %b / %B month name, %a / %A weekday name, %s epoch seconds, %z +0000, %Z UTC,
+// %F for %Y-%m-%d, %T for %H:%M:%S and %% for a percent sign
+pub fn strftime(timestamp: i64, format: &str) -> String {
+    let time = DateTime::new(timestamp);
+    let weekday = (timestamp.div_euclid(DAY) + 4).rem_euclid(7) as usize;
+    let hour12 = if time.hour % 12 == 0 { 12 } else { time.hour % 12 };
+
+    let mut result = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+
+        let text = match chars.next() {
+            Some('Y') => format!("{:04}", time.year),
+            Some('y') => format!("{:02}", time.year.rem_euclid(100)),
+            Some('m') => format!("{:02}", time.month),
+            Some('d') => format!("{:02}", time.day),
+            Some('e') => format!("{:2}", time.day),
+            Some('j') => format!("{:03}", days_from_civil(time.year, time.month, time.day) - days_from_civil(time.year, 1, 1) + 1),
+            Some('H') => format!("{:02}", time.hour),
+            Some('I') => format!("{:02}", hour12),
+            Some('p') => String::from(if time.hour < 12 { "AM" } else { "PM" }),
+            Some('M') => format!("{:02}", time.minute),
+            Some('S') => format!("{:02}", time.second),
+            Some('L') => format!("{:03}", time.millis),
+            Some('3') if chars.peek() == Some(&'N') => {
+                chars.next();
+                format!("{:03}", time.millis)
+            },
+            Some('b') => MONTHS[time.month as usize - 1][..3].to_string(),
+            Some('B') => MONTHS[time.month as usize - 1].to_string(),
+            Some('a') => WEEKDAYS[weekday][..3].to_string(),
+            Some('A') => WEEKDAYS[weekday].to_string(),
+            Some('s') => timestamp.div_euclid(SECOND).to_string(),
+            Some('z') => String::from("+0000"),
+            Some('Z') => String::from("UTC"),
+            Some('F') => format!("{:04}-{:02}-{:02}", time.year, time.month, time.day),
+            Some('T') => format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second),
+            Some('%') => String::from("%"),
+            // Unknown conversions are kept as they are
+            Some(other) => format!("%{}", other),
+            None => String::from("%")
+        };
+
+        result.push_str(&text);
+    }
+
+    return result;
+}
+
+// Reads text from the start of the input
+struct Input<'a> {
+    text: &'a str
+}
+
+impl Input<'_> {
+    fn literal(&mut self, literal: &str) -> Option<()> {
+        self.text = self.text.strip_prefix(literal)?;
+        return Some(());
+    }
+
+    // Up to max digits, at least one
+    fn digits(&mut self, max: usize) -> Option<(i64, usize)> {
+        let count = self.text.bytes().take(max).take_while(u8::is_ascii_digit).count();
+        if count == 0 {
+            return None;
+        }
+
+        let (digits, rest) = self.text.split_at(count);
+        self.text = rest;
+        return Some((digits.parse().ok()?, count));
+    }
+
+    fn number(&mut self, max: usize) -> Option<i64> {
+        return self.digits(max).map(|(number, _)| number);
+    }
+
+    // One of the names, or its first three letters, ignoring case. Returns the index of the name.
+    fn name(&mut self, names: &[&str]) -> Option<usize> {
+        for (index, name) in names.iter().enumerate() {
+            for candidate in [*name, name.get(..3).unwrap_or(name)] {
+                if self.text.get(..candidate.len()).is_some_and(|text| text.eq_ignore_ascii_case(candidate)) {
+                    self.text = &self.text[candidate.len()..];
+                    return Some(index);
+                }
+            }
+        }
+        return None;
+    }
+
+    // Z, or an offset such as +0100, -05:30 or +02
+    fn offset(&mut self) -> Option<i64> {
+        if self.literal("Z").is_some() {
+            return Some(0);
+        }
+
+        let sign = if self.literal("+").is_some() { 1 } else { self.literal("-").map(|_| -1)? };
+        let hours = self.number(2)?;
+        self.literal(":");
+        let minutes = self.number(2).unwrap_or(0);
+
+        return Some(sign * (hours * HOUR + minutes * MINUTE));
+    }
+}
+
+// Parses text with the conversions of strftime, None when it doesn't match the format.
+// %3N and %L read any number of fraction digits, %z also reads Z and offsets with a colon.
+// Runs of whitespace in the format match any whitespace in the text.
+pub fn strptime(text: &str, format: &str) -> Option<i64> {
+    let mut input = Input { text };
+    let mut time = DateTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0, millis: 0 };
+    let mut offset = 0;
+    let mut pm: Option<bool> = None;
+    let mut epoch: Option<i64> = None;
+
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            input.text = input.text.trim_start();
+            continue;
+        }
+
+        if c != '%' {
+            input.literal(c.encode_utf8(&mut [0; 4]))?;
+            continue;
+        }
+
+        match chars.next()? {
+            'Y' => {
+                let sign = if input.literal("-").is_some() { -1 } else { 1 };
+                time.year = sign * input.number(4)?;
+            },
+            // 69 to 99 are 1969 to 1999, 00 to 68 are 2000 to 2068
+            'y' => {
+                let year = input.number(2)?;
+                time.year = if year < 69 { 2000 + year } else { 1900 + year };
+            },
+            'm' => time.month = input.number(2)?,
+            'd' => time.day = input.number(2)?,
+            'e' => {
+                input.literal(" ");
+                time.day = input.number(2)?;
+            },
+            'H' => time.hour = input.number(2)?,
+            'I' => time.hour = input.number(2)?,
+            'p' => pm = Some(input.name(&["PM", "AM"])? == 0),
+            'M' => time.minute = input.number(2)?,
+            'S' => time.second = input.number(2)?,
+            'L' => time.millis = fraction(&mut input)?,
+            '3' if chars.peek() == Some(&'N') => {
+                chars.next();
+                time.millis = fraction(&mut input)?;
+            },
+            'b' | 'B' => time.month = input.name(&MONTHS)? as i64 + 1,
+            'a' | 'A' => { input.name(&WEEKDAYS)?; },
+            's' => {
+                let sign = if input.literal("-").is_some() { -1 } else { 1 };
+                epoch = Some(sign * input.number(18)?.checked_mul(SECOND)?);
+            },
+            'z' => offset = input.offset()?,
+            'Z' => {
+                if input.literal("UTC").is_none() && input.literal("GMT").is_none() {
+                    input.literal("Z")?;
+                }
+            },
+            'F' => {
+                time.year = input.number(4)?;
+                input.literal("-")?;
+                time.month = input.number(2)?;
+                input.literal("-")?;
+                time.day = input.number(2)?;
+            },
+            'T' => {
+                time.hour = input.number(2)?;
+                input.literal(":")?;
+                time.minute = input.number(2)?;
+                input.literal(":")?;
+                time.second = input.number(2)?;
+            },
+            '%' => input.literal("%")?,
+            _ => return None
+        }
+    }
+
+    if !input.text.is_empty() {
+        return None;
+    }
+
+    if let Some(epoch) = epoch {
+        return epoch.checked_add(time.millis);
+    }
+
+    // 12 AM is midnight and 12 PM is noon
+    if let Some(pm) = pm {
+        if !(1..=12).contains(&time.hour) {
+            return None;
+        }
+        time.hour = time.hour % 12 + if pm { 12 } else { 0 };
+    }
+
+    let valid = (1..=12).contains(&time.month)
+        && (1..=days_in_month(time.year, time.month)).contains(&time.day)
+        && time.hour < 24 && time.minute < 60 && time.second < 60;
+
+    if !valid {
+        return None;
+    }
+
+    return time.timestamp()?.checked_sub(offset);
+}
+
+// Fraction of a second as milliseconds, e.g. 5 is 500 and 123456 is 123
+fn fraction(input: &mut Input) -> Option<i64> {
+    let (digits, count) = input.digits(9)?;
+    return Some(match count {
+        1 => digits * 100,
+        2 => digits * 10,
+        count => digits / 10_i64.pow(count as u32 - 3)
+    });
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    // With the first day of the week when snapping, 0 for Sunday
+    Week(i64),
+    Month,
+    Quarter,
+    Year
+}
+
+fn unit(name: &str) -> Option<Unit> {
+    return Some(match name {
+        "s" | "sec" | "secs" | "second" | "seconds" => Unit::Second,
+        "m" | "min" | "mins" | "minute" | "minutes" => Unit::Minute,
+        "h" | "hr" | "hrs" | "hour" | "hours" => Unit::Hour,
+        "d" | "day" | "days" => Unit::Day,
+        "w" | "week" | "weeks" => Unit::Week(0),
+        "mon" | "month" | "months" => Unit::Month,
+        "q" | "qtr" | "quarter" | "quarters" => Unit::Quarter,
+        "y" | "yr" | "year" | "years" => Unit::Year,
+        name => match name.strip_prefix('w').and_then(|day| day.parse::<i64>().ok()) {
+            Some(day) if (0..7).contains(&day) => Unit::Week(day),
+            _ => return None
+        }
+    });
+}
+
+// Moves the timestamp by a number of units, months keep their day when it exists (Jan 31 + 1mon is Feb 28/29)
+fn shift(timestamp: i64, amount: i64, unit: Unit) -> Option<i64> {
+    let fixed = match unit {
+        Unit::Second => SECOND,
+        Unit::Minute => MINUTE,
+        Unit::Hour => HOUR,
+        Unit::Day => DAY,
+        Unit::Week(_) => WEEK,
+        Unit::Month => return shift_months(timestamp, amount),
+        Unit::Quarter => return shift_months(timestamp, amount.checked_mul(3)?),
+        Unit::Year => return shift_months(timestamp, amount.checked_mul(12)?)
+    };
+
+    return timestamp.checked_add(amount.checked_mul(fixed)?);
+}
+
+fn shift_months(timestamp: i64, months: i64) -> Option<i64> {
+    let mut time = DateTime::new(timestamp);
+    let month = (time.year.checked_mul(12)? + time.month - 1).checked_add(months)?;
+
+    time.year = month.div_euclid(12);
+    // Far beyond the range of timestamps, and the calendar arithmetic would overflow
+    if time.year.abs() > 300_000_000 {
+        return None;
+    }
+    time.month = month.rem_euclid(12) + 1;
+    time.day = time.day.min(days_in_month(time.year, time.month));
+
+    return time.timestamp();
+}
+
+// Rounds the timestamp down to the start of the unit
+fn snap(timestamp: i64, unit: Unit) -> Option<i64> {
+    let start_of_day = timestamp.div_euclid(DAY) * DAY;
+    let time = DateTime::new(timestamp);
+
+    return match unit {
+        Unit::Second => Some(timestamp.div_euclid(SECOND) * SECOND),
+        Unit::Minute => Some(timestamp.div_euclid(MINUTE) * MINUTE),
+        Unit::Hour => Some(timestamp.div_euclid(HOUR) * HOUR),
+        Unit::Day => Some(start_of_day),
+        Unit::Week(first_day) => {
+            let weekday = (timestamp.div_euclid(DAY) + 4).rem_euclid(7);
+            start_of_day.checked_sub((weekday - first_day).rem_euclid(7) * DAY)
+        },
+        Unit::Month => DateTime { day: 1, hour: 0, minute: 0, second: 0, millis: 0, ..time }.timestamp(),
+        Unit::Quarter => DateTime { month: (time.month - 1) / 3 * 3 + 1, day: 1, hour: 0, minute: 0, second: 0, millis: 0, ..time }.timestamp(),
+        Unit::Year => DateTime { month: 1, day: 1, hour: 0, minute: 0, second: 0, millis: 0, ..time }.timestamp()
+    };
+}
+
+// Applies a relative time specifier, a list of offsets such as -1d or +30m and snaps such as @d, e.g.
+// -1d@d is the start of yesterday, @w1 the start of this week on Monday and @mon+9h the first of the month at 9.
+// "now" and "" leave the timestamp as it is.
+pub fn relative_time(timestamp: i64, specifier: &str) -> Result<i64, Box<dyn Error>> {
+    let invalid = || format!("expects a relative time specifier such as -1d@d, got \"{}\"", specifier);
+
+    if specifier == "now" {
+        return Ok(timestamp);
+    }
+
+    let mut result = timestamp;
+    let mut rest = specifier;
+
+    while !rest.is_empty() {
+        // Read by character, so text that isn't ASCII is an error rather than a bad slice
+        let operator = match rest.chars().next() {
+            Some(operator @ ('+' | '-' | '@')) => operator,
+            _ => return Err(invalid().into())
+        };
+        let after = &rest[operator.len_utf8()..];
+
+        let amount_end = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+        let (amount, after) = after.split_at_checked(amount_end).ok_or_else(invalid)?;
+        let mut unit_end = after.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(after.len());
+        // w0 to w6 are units of their own
+        if after.get(..unit_end) == Some("w") && after.get(unit_end..).is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit())) {
+            unit_end += 1;
+        }
+        let (name, after) = after.split_at_checked(unit_end).ok_or_else(invalid)?;
+        let unit = unit(name).ok_or_else(invalid)?;
+
+        result = match operator {
+            '@' if amount.is_empty() => snap(result, unit),
+            '@' => return Err(invalid().into()),
+            _ => {
+                let amount = if amount.is_empty() { 1 } else { amount.parse::<i64>().map_err(|_| invalid())? };
+                shift(result, if operator == '-' { -amount } else { amount }, unit)
+            }
+        }.ok_or(format!("gives a time out of range for \"{}\"", specifier))?;
+
+        rest = after;
+    }
+
+    return Ok(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> i64 {
+        return parse_timestamp(text).unwrap();
+    }
+
+    #[test]
+    fn relative_time_snaps_and_shifts() {
+        let time = at("2024-03-13T15:45:30Z");
+        assert_eq!(relative_time(time, "-1d@d").unwrap(), at("2024-03-12"));
+        assert_eq!(relative_time(time, "@h+30m").unwrap(), at("2024-03-13T15:30:00Z"));
+        assert_eq!(relative_time(time, "@w1").unwrap(), at("2024-03-11"));
+        assert_eq!(relative_time(at("2024-01-31"), "+1mon").unwrap(), at("2024-02-29"));
+        assert_eq!(relative_time(time, "now").unwrap(), time);
+    }
+
+    #[test]
+    fn relative_time_rejects_invalid_specifiers() {
+        for specifier in ["é", "+é", "-1é", "+1w\u{e9}", "1d", "@1d", "-1x", "+"] {
+            assert!(relative_time(0, specifier).is_err(), "{}", specifier);
+        }
+    }
+
+    #[test]
+    fn strftime_and_strptime_round_trip() {
+        let time = at("2024-03-01T09:05:07.250Z");
+        assert_eq!(strftime(time, ISO_8601), "2024-03-01T09:05:07.250Z");
+        assert_eq!(strftime(time, "%a %d %b %y %I%p %j"), "Fri 01 Mar 24 09AM 061");
+        assert_eq!(strptime("01/03/2024 09:05", "%d/%m/%Y %H:%M"), Some(at("2024-03-01T09:05:00Z")));
+        assert_eq!(strptime("2024-03-01T10:05:07+01:00", "%Y-%m-%dT%H:%M:%S%z"), Some(at("2024-03-01T09:05:07Z")));
+        assert_eq!(strptime("2024-02-30", "%Y-%m-%d"), None);
+        assert_eq!(strptime("PM", "%p"), None);
+    }
+
+    #[test]
+    fn parses_durations_and_timestamps() {
+        assert_eq!(parse_duration("5m"), Some(Value::Int(300)));
+        assert_eq!(parse_duration("250ms"), Some(Value::Float(0.25)));
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_timestamp("86400"), Some(DAY));
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+}
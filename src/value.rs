@@ -0,0 +1,330 @@
+// The typed value held by every table cell and produced by every evaluation.
+//
+// Coercion rules:
+// - Literals keep the type they were written with, "007" is a string and 7 is a number.
+// - Arithmetic between two ints stays an int, any float involved makes the result a float.
+// - Int arithmetic that overflows 64 bits is promoted to a float instead of wrapping.
+// - Dividing two ints that don't divide evenly gives a float, dividing by zero gives null.
+// - Strings holding a (finite) number take part in arithmetic as that number, except for + which concatenates.
+//   Text such as "inf" or "nan" is not a number.
+// - Comparing a string with a number compares numerically if the string is a number, otherwise as text.
+// - Null propagates through arithmetic, and every comparison involving null is false.
+// - Adding or subtracting a number of seconds to a timestamp gives a timestamp, subtracting two timestamps the seconds between them.
+
+#![allow(clippy::needless_return)]
+
+use std::{cmp::Ordering, error::Error, fmt::Display, hash::{Hash, Hasher}};
+
+use crate::time;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    // Milliseconds since the Unix epoch, in UTC
+    Timestamp(i64),
+    Multivalue(Vec<Value>)
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        return match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Timestamp(_) => "timestamp",
+            Value::Multivalue(_) => "multivalue"
+        };
+    }
+
+    pub fn is_null(&self) -> bool {
+        return matches!(self, Value::Null);
+    }
+
+    pub fn is_number(&self) -> bool {
+        return matches!(self, Value::Int(_) | Value::Float(_));
+    }
+
+    // Numeric value of numbers and of strings holding a number, "inf" and "nan" are text
+    pub fn as_number(&self) -> Option<f64> {
+        return match self {
+            Value::Int(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            Value::String(value) => value.trim().parse::<f64>().ok().filter(|value| value.is_finite()),
+            _ => None
+        };
+    }
+
+    // Parses a number literal, ints that don't fit in 64 bits become floats.
+    // Text such as "inf", "NaN" or "1e999" isn't a number.
+    pub fn parse_number(text: &str) -> Option<Value> {
+        if let Ok(value) = text.parse::<i64>() {
+            return Some(Value::Int(value));
+        }
+        return text.parse::<f64>().ok().filter(|value| value.is_finite()).map(Value::Float);
+    }
+
+    // The value as an int or float, if it is one (or a string holding one)
+    pub fn to_number(&self) -> Option<Value> {
+        return match self {
+            Value::Int(_) | Value::Float(_) => Some(self.clone()),
+            Value::String(value) => Value::parse_number(value.trim()),
+            _ => None
+        };
+    }
+
+    // Numbers are added, anything else is concatenated as text
+    pub fn add(&self, other: &Value) -> Result<Value, Box<dyn Error>> {
+        return match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (Value::Timestamp(timestamp), seconds) | (seconds, Value::Timestamp(timestamp)) if seconds.is_number() =>
+                Ok(Self::shift_timestamp(*timestamp, seconds.as_number().unwrap())),
+            (Value::String(_), _) | (_, Value::String(_)) => Ok(Value::String(format!("{}{}", self, other))),
+            _ => self.arithmetic(other, "add", i64::checked_add, |a, b| a + b)
+        };
+    }
+
+    pub fn sub(&self, other: &Value) -> Result<Value, Box<dyn Error>> {
+        return match (self, other) {
+            (Value::Timestamp(a), Value::Timestamp(b)) => Ok(a.checked_sub(*b).map(time::to_seconds).unwrap_or(Value::Null)),
+            (Value::Timestamp(timestamp), seconds) if seconds.is_number() =>
+                Ok(Self::shift_timestamp(*timestamp, -seconds.as_number().unwrap())),
+            _ => self.arithmetic(other, "subtract", i64::checked_sub, |a, b| a - b)
+        };
+    }
+
+    // Null when the result is out of range
+    fn shift_timestamp(timestamp: i64, seconds: f64) -> Value {
+        return time::from_seconds(seconds)
+            .and_then(|millis| timestamp.checked_add(millis))
+            .map(Value::Timestamp)
+            .unwrap_or(Value::Null);
+    }
+
+    pub fn mul(&self, other: &Value) -> Result<Value, Box<dyn Error>> {
+        return self.arithmetic(other, "multiply", i64::checked_mul, |a, b| a * b);
+    }
+
+    // Ints only stay ints when they divide evenly, so 7 / 2 is 3.5
+    pub fn div(&self, other: &Value) -> Result<Value, Box<dyn Error>> {
+        if self.is_divide_by_zero(other) {
+            return Ok(Value::Null);
+        }
+        return self.arithmetic(other, "divide", |a, b| if a.checked_rem(b) == Some(0) { a.checked_div(b) } else { None }, |a, b| a / b);
+    }
+
+    // The result has the sign of the left side, so -7 % 3 is -1
+    pub fn rem(&self, other: &Value) -> Result<Value, Box<dyn Error>> {
+        if self.is_divide_by_zero(other) {
+            return Ok(Value::Null);
+        }
+        return self.arithmetic(other, "take the remainder of", i64::checked_rem, |a, b| a % b);
+    }
+
+    // Negative exponents always give a float
+    pub fn pow(&self, other: &Value) -> Result<Value, Box<dyn Error>> {
+        return self.arithmetic(other, "raise", |a, b| u32::try_from(b).ok().and_then(|b| a.checked_pow(b)), f64::powf);
+    }
+
+    // Dividing a number by zero gives null, dividing anything else is still a type error
+    fn is_divide_by_zero(&self, other: &Value) -> bool {
+        return self.to_number().is_some() && other.as_number() == Some(0.0);
+    }
+
+    pub fn negate(&self) -> Result<Value, Box<dyn Error>> {
+        return Value::Int(0).sub(self);
+    }
+
+    // Applies the int operation when both sides are ints (falling back to floats on overflow), otherwise the float one
+    fn arithmetic(&self, other: &Value, verb: &str, int_op: fn(i64, i64) -> Option<i64>, float_op: fn(f64, f64) -> f64) -> Result<Value, Box<dyn Error>> {
+        if self.is_null() || other.is_null() {
+            return Ok(Value::Null);
+        }
+
+        return match (self.to_number(), other.to_number()) {
+            (Some(Value::Int(a)), Some(Value::Int(b))) => Ok(match int_op(a, b) {
+                Some(result) => Value::Int(result),
+                None => Value::Float(float_op(a as f64, b as f64))
+            }),
+            (Some(a), Some(b)) => Ok(Value::Float(float_op(a.as_number().unwrap(), b.as_number().unwrap()))),
+            _ => Err(format!("Cannot {} {} \"{}\" and {} \"{}\"", verb, self.type_name(), self, other.type_name(), other).into())
+        };
+    }
+
+    // Compares two values following the coercion rules, None if they can't be compared
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        return match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => None,
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+            (Value::Multivalue(_), _) | (_, Value::Multivalue(_)) => None,
+            (a, b) if a.is_number() || b.is_number() => match (a.as_number(), b.as_number()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => Some(a.to_string().cmp(&b.to_string()))
+            },
+            (a, b) => Some(a.to_string().cmp(&b.to_string()))
+        };
+    }
+
+    // Orders any two values, those that can't be compared (e.g. null or multivalues) by their text
+    pub fn order(&self, other: &Value) -> Ordering {
+        return self.compare(other).unwrap_or_else(|| self.to_string().cmp(&other.to_string()));
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Timestamp(value) => write!(f, "{}", time::strftime(*value, time::ISO_8601)),
+            Value::Multivalue(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "{}", values.join(", "))
+            }
+        }
+    }
+}
+
+// Structural equality, so 1 and 1.0 are different values (use compare for numeric equality)
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        return match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
+            (Value::Multivalue(a), Value::Multivalue(b)) => a == b,
+            _ => false
+        };
+    }
+}
+
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {},
+            Value::Bool(value) => value.hash(state),
+            Value::Int(value) => value.hash(state),
+            Value::Float(value) => value.to_bits().hash(state),
+            Value::String(value) => value.hash(state),
+            Value::Timestamp(value) => value.hash(state),
+            Value::Multivalue(values) => values.hash(state)
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        return Value::String(value.to_string());
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        return Value::String(value);
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        return Value::Int(value);
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        return Value::Float(value);
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        return Value::Bool(value);
+    }
+}
+
+// Timestamps become ISO 8601 strings and floats that aren't a real number become null
+impl From<&Value> for serde_json::Value {
+    fn from(value: &Value) -> Self {
+        return match value {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(value) => serde_json::Value::Bool(*value),
+            Value::Int(value) => serde_json::Value::from(*value),
+            Value::Float(value) => serde_json::Number::from_f64(*value).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+            Value::String(value) => serde_json::Value::String(value.clone()),
+            Value::Timestamp(_) => serde_json::Value::String(value.to_string()),
+            Value::Multivalue(values) => serde_json::Value::Array(values.iter().map(serde_json::Value::from).collect())
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_only_finite_numbers_from_text() {
+        assert_eq!(Value::parse_number("42"), Some(Value::Int(42)));
+        assert_eq!(Value::parse_number("-2.5"), Some(Value::Float(-2.5)));
+        assert_eq!(Value::parse_number("99999999999999999999"), Some(Value::Float(1e20)));
+        assert_eq!(Value::from(" 7 ").to_number(), Some(Value::Int(7)));
+
+        for text in ["inf", "-infinity", "NaN", "1e999", "", "7a"] {
+            assert_eq!(Value::parse_number(text), None, "{}", text);
+            assert_eq!(Value::from(text).as_number(), None, "{}", text);
+            assert_eq!(Value::from(text).to_number(), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn follows_the_coercion_rules() {
+        assert_eq!(Value::Int(2).add(&Value::Int(3)).unwrap(), Value::Int(5));
+        assert_eq!(Value::Int(2).add(&Value::Float(0.5)).unwrap(), Value::Float(2.5));
+        assert_eq!(Value::Int(i64::MAX).add(&Value::Int(1)).unwrap(), Value::Float(i64::MAX as f64 + 1.0));
+        assert_eq!(Value::Int(7).div(&Value::Int(2)).unwrap(), Value::Float(3.5));
+        assert_eq!(Value::Int(6).div(&Value::Int(2)).unwrap(), Value::Int(3));
+        assert_eq!(Value::Int(1).div(&Value::Int(0)).unwrap(), Value::Null);
+        assert_eq!(Value::Int(-7).rem(&Value::Int(3)).unwrap(), Value::Int(-1));
+        assert_eq!(Value::from("4").mul(&Value::Int(2)).unwrap(), Value::Int(8));
+        assert_eq!(Value::from("4").add(&Value::Int(2)).unwrap(), Value::from("42"));
+        assert_eq!(Value::Null.sub(&Value::Int(1)).unwrap(), Value::Null);
+        assert!(Value::from("inf").mul(&Value::Int(2)).is_err());
+        assert!(Value::Bool(true).sub(&Value::Int(1)).is_err());
+
+        assert_eq!(Value::Timestamp(1_000).add(&Value::Int(2)).unwrap(), Value::Timestamp(3_000));
+        assert_eq!(Value::Timestamp(5_000).sub(&Value::Timestamp(2_000)).unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn compares_numbers_numerically_and_text_as_text() {
+        assert_eq!(Value::from("10").compare(&Value::Int(9)), Some(Ordering::Greater));
+        assert_eq!(Value::from("10").compare(&Value::from("9")), Some(Ordering::Less));
+        // "inf" is text, so it is compared with the text of 9
+        assert_eq!(Value::from("inf").compare(&Value::Int(9)), Some(Ordering::Greater));
+        assert_eq!(Value::Int(1).compare(&Value::Float(1.0)), Some(Ordering::Equal));
+        assert_eq!(Value::Null.compare(&Value::Null), None);
+    }
+
+    #[test]
+    fn orders_values_that_cant_be_compared_by_their_text() {
+        assert_eq!(Value::Int(10).order(&Value::Int(9)), Ordering::Greater);
+        assert_eq!(Value::Multivalue(vec![Value::Int(2)]).order(&Value::Int(10)), Ordering::Greater);
+        assert_eq!(Value::Null.order(&Value::from("a")), Ordering::Less);
+    }
+}
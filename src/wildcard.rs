@@ -0,0 +1,80 @@
+// Field name patterns, where '*' matches any (possibly empty) run of characters.
+// Examples:
+// src_*   matches src_ip, src_port
+// *_time  matches start_time, end_time
+
+#![allow(clippy::needless_return)]
+
+/// Returns the text matched by every '*' in the pattern, or None if the name does not match
+pub fn captures(pattern: &str, name: &str) -> Option<Vec<String>> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let mut result = Vec::new();
+
+    if capture_from(&pattern, &name, &mut result) {
+        return Some(result);
+    }
+
+    return None;
+}
+
+fn capture_from(pattern: &[char], name: &[char], result: &mut Vec<String>) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => {
+            // Try the shortest capture first, so every star is as lazy as possible
+            for split in 0..=name.len() {
+                result.push(name[..split].iter().collect());
+                if capture_from(&pattern[1..], &name[split..], result) {
+                    return true;
+                }
+                result.pop();
+            }
+            false
+        },
+        Some(c) => name.first() == Some(c) && capture_from(&pattern[1..], &name[1..], result)
+    }
+}
+
+pub fn is_match(pattern: &str, name: &str) -> bool {
+    return captures(pattern, name).is_some();
+}
+
+/// Fills every '*' in the template with the next capture, in order
+pub fn substitute(template: &str, captures: &[String]) -> String {
+    let mut result = String::new();
+    let mut captures = captures.iter();
+
+    for c in template.chars() {
+        if c == '*' {
+            result.push_str(captures.next().map(|s| s.as_str()).unwrap_or(""));
+        } else {
+            result.push(c);
+        }
+    }
+
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_every_star_lazily() {
+        assert_eq!(captures("src_*", "src_ip"), Some(vec!["ip".to_string()]));
+        assert_eq!(captures("*_*", "a_b_c"), Some(vec!["a".to_string(), "b_c".to_string()]));
+        assert_eq!(captures("*", ""), Some(vec!["".to_string()]));
+        assert_eq!(captures("é*", "éa"), Some(vec!["a".to_string()]));
+        assert_eq!(captures("src_*", "dst_ip"), None);
+        assert!(is_match("*_time", "start_time"));
+        assert!(!is_match("host", "hostname"));
+    }
+
+    #[test]
+    fn substitutes_captures_in_order() {
+        assert_eq!(substitute("*-*", &["a".to_string(), "b".to_string()]), "a-b");
+        assert_eq!(substitute("x_*_*", &["a".to_string()]), "x_a_");
+        assert_eq!(substitute("plain", &[]), "plain");
+    }
+}